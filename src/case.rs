@@ -0,0 +1,54 @@
+/// The words of an identifier, split at non-alphanumeric characters and at case changes: `HTTPRequest2fa` gives
+/// `HTTP`, `Request2fa`.
+pub fn words(string: &str) -> Vec<String> {
+    let chars: Vec<char> = string.chars().collect();
+    let mut words: Vec<String> = Vec::new();
+    let mut word = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if !c.is_alphanumeric() {
+            if !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            continue;
+        }
+        let boundary = c.is_uppercase()
+            && i > 0
+            && (chars[i - 1].is_lowercase()
+                || chars[i - 1].is_ascii_digit()
+                || chars[i - 1].is_uppercase() && chars.get(i + 1).is_some_and(|n| n.is_lowercase()));
+        if boundary && !word.is_empty() {
+            words.push(std::mem::take(&mut word));
+        }
+        word.push(c);
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+/// `string` in PascalCase, prefixed with `V` if it would start with a digit.
+pub fn pascal_case(string: &str) -> String {
+    let name: String = words(string)
+        .iter()
+        .map(|w| {
+            let mut chars = w.chars();
+            let first = chars.next().unwrap().to_uppercase();
+            format!("{first}{}", chars.as_str().to_lowercase())
+        })
+        .collect();
+    match name.chars().next() {
+        None => "Empty".to_string(),
+        Some(c) if c.is_ascii_digit() => format!("V{name}"),
+        _ => name,
+    }
+}
+
+/// `string` in lower snake case.
+pub fn snake_case(string: &str) -> String {
+    words(string)
+        .iter()
+        .map(|w| w.to_lowercase())
+        .collect::<Vec<String>>()
+        .join("_")
+}
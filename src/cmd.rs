@@ -1,32 +1,514 @@
-use std::{env, process};
-
-#[derive(Copy, Clone)]
-pub enum Extension {
-    JS,
-    TS,
-    DTS,
-}
-
-pub fn input() -> (String, String, Extension) {
-    let args: Vec<String> = env::args().collect();
-    if let (Some(f), Some(w)) = (args.get(1), args.get(2)) {
-        (f.clone(), w.clone(), get_extension(w.clone()))
-    } else {
-        eprintln!("{}", USAGE);
-        process::exit(0);
-    }
-}
-
-pub fn get_extension(file_path: String) -> Extension {
-    if file_path.ends_with(".js") {
-        return Extension::JS;
-    } else if file_path.ends_with(".d.ts") {
-        return Extension::DTS;
-    } else if file_path.ends_with(".ts") {
-        return Extension::TS;
-    } else {
-        panic!("File extension is not .ts or .js");
-    }
-}
-
-pub const USAGE: &str = "USAGE: ts-runtime-typechecker <READ-FILE-PATH> <WRITE-FILE-PATH>";
+use crate::case::{snake_case, words};
+use std::path::{Component, Path};
+use std::{env, fs, process};
+
+#[derive(Copy, Clone, Default)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum Extension {
+    JS,
+    #[default]
+    TS,
+    DTS,
+}
+
+/// What the generated file contains.
+#[derive(Copy, Clone, Default, PartialEq)]
+pub enum Format {
+    /// `is<Name>` type guards, plus the validators, assertions and parsers asked for.
+    #[default]
+    Guards,
+    /// `<Name>Schema` zod schemas.
+    Zod,
+    /// A JSON Schema with one definition per interface.
+    JsonSchema,
+    /// Rust types with serde derives.
+    Rust,
+    /// The `components.schemas` of an OpenAPI document, as YAML if the output path ends in `.yaml` or `.yml`.
+    OpenApi,
+    /// Python `TypedDict` classes.
+    TypedDict,
+    /// Python pydantic models.
+    Pydantic,
+}
+
+/// How the generated JavaScript exposes its functions.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum Module {
+    #[default]
+    Esm,
+    CommonJs,
+    /// An IIFE adding the functions to the object at `globalThis[name]`.
+    Global(String),
+}
+
+/// How the generated JavaScript is laid out, named after the prettier options.
+#[derive(Clone)]
+pub struct Style {
+    /// Keep each function on one line, as it is generated.
+    pub minify: bool,
+    pub print_width: usize,
+    pub tab_width: usize,
+    pub use_tabs: bool,
+    pub single_quote: bool,
+    pub semi: bool,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Style {
+            minify: false,
+            print_width: 120,
+            tab_width: 4,
+            use_tabs: false,
+            single_quote: false,
+            semi: true,
+        }
+    }
+}
+
+/// Templates for the names of the generated functions. `{Name}` stands for the interface name as declared, `{name}`
+/// for the same with its first word in lower case and `{snake_name}` for it in snake case.
+#[derive(Clone)]
+pub struct Naming {
+    pub guard: String,
+    pub validator: String,
+    pub assertion: String,
+    pub parser: String,
+    pub safe_parser: String,
+}
+
+impl Default for Naming {
+    fn default() -> Self {
+        Naming {
+            guard: "is{Name}".to_string(),
+            validator: "validate{Name}".to_string(),
+            assertion: "assert{Name}".to_string(),
+            parser: "parse{Name}".to_string(),
+            safe_parser: "safeParse{Name}".to_string(),
+        }
+    }
+}
+
+impl Naming {
+    pub fn guard(&self, name: &str) -> String {
+        fill(&self.guard, name)
+    }
+
+    pub fn validator(&self, name: &str) -> String {
+        fill(&self.validator, name)
+    }
+
+    pub fn assertion(&self, name: &str) -> String {
+        fill(&self.assertion, name)
+    }
+
+    pub fn parser(&self, name: &str) -> String {
+        fill(&self.parser, name)
+    }
+
+    pub fn safe_parser(&self, name: &str) -> String {
+        fill(&self.safe_parser, name)
+    }
+}
+
+fn fill(template: &str, name: &str) -> String {
+    let words = words(name);
+    let lower = match words.first() {
+        Some(first) if name.starts_with(first.as_str()) => format!("{}{}", first.to_lowercase(), &name[first.len()..]),
+        _ => name.to_string(),
+    };
+    template
+        .replace("{Name}", name)
+        .replace("{name}", &lower)
+        .replace("{snake_name}", &snake_case(name))
+}
+
+/// A name that is either global or imported from `module` in the generated file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Import {
+    pub name: String,
+    pub module: Option<String>,
+}
+
+impl Import {
+    pub fn parse(string: &str) -> Import {
+        match string.split_once(':') {
+            Some((name, module)) => Import {
+                name: name.to_string(),
+                module: Some(module.to_string()),
+            },
+            None => Import {
+                name: string.to_string(),
+                module: None,
+            },
+        }
+    }
+}
+
+#[derive(Default, Clone)]
+pub struct Options {
+    pub read_path: String,
+    pub write_path: String,
+    pub extension: Extension,
+    pub format: Format,
+    pub module: Module,
+    /// Write one module per interface and an `index.ts` into the directory `write_path`.
+    pub out_dir: bool,
+    /// Also write a `.d.ts` file next to the `.js` output.
+    pub declarations: bool,
+    pub style: Style,
+    pub naming: Naming,
+    /// Also write a `.map` file mapping each property check to the declaration of the property.
+    pub source_map: bool,
+    /// Also write a `.test.ts` file checking the guards against made up valid and invalid values.
+    pub emit_tests: bool,
+    pub validators: bool,
+    pub assertions: Option<Import>,
+    pub parsers: bool,
+    pub revive: bool,
+    pub inherited_keys: bool,
+    pub plain_objects: bool,
+    /// Refinement functions for branded types, by brand name.
+    pub brands: Vec<(String, Import)>,
+    /// Guards for types that aren't declared in the input, by type name.
+    pub guards: Vec<(String, Import)>,
+}
+
+pub fn input() -> Options {
+    let mut options = Options::default();
+    let mut paths: Vec<String> = Vec::new();
+    let mut format = None;
+    for arg in env::args().skip(1) {
+        let (flag, value) = match arg.split_once('=') {
+            Some((flag, value)) => (flag, Some(value)),
+            None => (arg.as_str(), None),
+        };
+        match (flag, value) {
+            ("--validators", None) => options.validators = true,
+            ("--assertions", None) => {
+                options.validators = true;
+                options.assertions.get_or_insert(Import::parse("TypeError"));
+            }
+            ("--assert-error", Some(class)) => {
+                options.validators = true;
+                options.assertions = Some(Import::parse(class));
+            }
+            ("--parsers", None) => {
+                options.validators = true;
+                options.parsers = true;
+            }
+            ("--revive", None) => {
+                options.validators = true;
+                options.parsers = true;
+                options.revive = true;
+            }
+            ("--format", Some(name)) => {
+                format = Some(match name {
+                    "guards" => Format::Guards,
+                    "zod" => Format::Zod,
+                    "json-schema" => Format::JsonSchema,
+                    "rust" => Format::Rust,
+                    "openapi" => Format::OpenApi,
+                    "typeddict" => Format::TypedDict,
+                    "pydantic" => Format::Pydantic,
+                    _ => {
+                        eprintln!("Unknown format: {}\n{}", name, USAGE);
+                        process::exit(1);
+                    }
+                })
+            }
+            ("--module", Some("esm")) => options.module = Module::Esm,
+            ("--module", Some("commonjs")) => options.module = Module::CommonJs,
+            ("--module", Some(module)) if module.starts_with("global=") && module.len() > "global=".len() => {
+                options.module = Module::Global(module["global=".len()..].to_string())
+            }
+            ("--declarations", None) => options.declarations = true,
+            ("--minify", None) => options.style.minify = true,
+            ("--print-width", Some(n)) => options.style.print_width = number(&arg, n),
+            ("--tab-width", Some(n)) => options.style.tab_width = number(&arg, n),
+            ("--use-tabs", None) => options.style.use_tabs = true,
+            ("--single-quote", None) => options.style.single_quote = true,
+            ("--no-semi", None) => options.style.semi = false,
+            ("--source-map", None) => options.source_map = true,
+            ("--emit-tests", None) => options.emit_tests = true,
+            ("--guard-name", Some(template)) => options.naming.guard = name_template(&arg, template),
+            ("--validator-name", Some(template)) => options.naming.validator = name_template(&arg, template),
+            ("--assertion-name", Some(template)) => options.naming.assertion = name_template(&arg, template),
+            ("--parser-name", Some(template)) => options.naming.parser = name_template(&arg, template),
+            ("--safe-parser-name", Some(template)) => options.naming.safe_parser = name_template(&arg, template),
+            ("--inherited-keys", None) => options.inherited_keys = true,
+            ("--plain-objects", None) => options.plain_objects = true,
+            ("--brand", Some(brand)) => match brand.split_once('=') {
+                Some((name, function)) => options.brands.push((name.to_string(), Import::parse(function))),
+                None => {
+                    eprintln!(
+                        "Expected --brand=<BRAND>=<FUNCTION>[:<MODULE>], found: {}\n{}",
+                        arg, USAGE
+                    );
+                    process::exit(1);
+                }
+            },
+            ("--guards", Some(path)) => match fs::read_to_string(path) {
+                Ok(config) => options.guards.extend(parse_guards(&config)),
+                Err(err) => {
+                    eprintln!("Could not read {}: {}", path, err);
+                    process::exit(1);
+                }
+            },
+            ("-h" | "--help", None) => {
+                eprintln!("{}", USAGE);
+                process::exit(0);
+            }
+            _ if arg.starts_with("--") => {
+                eprintln!("Unknown option: {}\n{}", arg, USAGE);
+                process::exit(1);
+            }
+            _ => paths.push(arg),
+        }
+    }
+    if let (Some(f), Some(w)) = (paths.first(), paths.get(1)) {
+        options.read_path = f.clone();
+        options.write_path = w.clone();
+        options.format = format.unwrap_or_else(|| get_format(w));
+        options.out_dir = w.ends_with(['/', '\\']) || Path::new(w).is_dir();
+        if options.out_dir && options.format != Format::Guards {
+            eprintln!("Only type guards can be written to a directory\n{}", USAGE);
+            process::exit(1);
+        }
+        if options.out_dir {
+            options.extension = Extension::TS;
+        } else if let Format::Guards | Format::Zod = options.format {
+            options.extension = get_extension(w.clone());
+        }
+        if options.module != Module::Esm && (options.format != Format::Guards || !w.ends_with(".js")) {
+            eprintln!("--module only applies to type guards written to a .js file\n{}", USAGE);
+            process::exit(1);
+        }
+        if options.declarations && (options.format != Format::Guards || !w.ends_with(".js")) {
+            eprintln!(
+                "--declarations only applies to type guards written to a .js file\n{}",
+                USAGE
+            );
+            process::exit(1);
+        }
+        if options.source_map
+            && (options.format != Format::Guards || options.out_dir || matches!(options.extension, Extension::DTS))
+        {
+            eprintln!(
+                "--source-map only applies to type guards written to a .js or .ts file\n{}",
+                USAGE
+            );
+            process::exit(1);
+        }
+        if options.emit_tests
+            && (options.format != Format::Guards
+                || matches!(options.extension, Extension::DTS)
+                || matches!(options.module, Module::Global(_)))
+        {
+            eprintln!(
+                "--emit-tests only applies to type guards written to a module or a directory\n{}",
+                USAGE
+            );
+            process::exit(1);
+        }
+        let imported = options
+            .assertions
+            .iter()
+            .chain(options.brands.iter().map(|(_, f)| f))
+            .chain(options.guards.iter().map(|(_, f)| f))
+            .find(|i| i.module.is_some());
+        if let (Module::Global(_), Some(import)) = (&options.module, imported) {
+            eprintln!(
+                "{} can't be imported from {} with --module=global, use a global instead",
+                import.name,
+                import.module.as_ref().unwrap()
+            );
+            process::exit(1);
+        }
+        options
+    } else {
+        eprintln!("{}", USAGE);
+        process::exit(0);
+    }
+}
+
+fn number(arg: &str, value: &str) -> usize {
+    value.parse().unwrap_or_else(|_| {
+        eprintln!("Expected a number, found: {}\n{}", arg, USAGE);
+        process::exit(1);
+    })
+}
+
+/// A naming template must contain the interface name and give an identifier.
+fn name_template(arg: &str, template: &str) -> String {
+    let filled = fill(template, "Name");
+    let identifier = !filled.starts_with(|c: char| c.is_ascii_digit())
+        && filled.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '$');
+    if filled == template || !identifier {
+        eprintln!(
+            "Expected a template with {{Name}}, {{name}} or {{snake_name}} that gives an identifier, found: {}\n{}",
+            arg, USAGE
+        );
+        process::exit(1);
+    }
+    template.to_string()
+}
+
+/// Reads a guards file, one `<TYPE> = <FUNCTION> [from "<MODULE>"]` per line. Blank lines and lines starting with
+/// `#` or `//` are ignored.
+pub fn parse_guards(config: &str) -> Vec<(String, Import)> {
+    config
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#') && !line.starts_with("//"))
+        .map(|(i, line)| {
+            let parsed = line.split_once('=').and_then(|(name, guard)| {
+                let (function, module) = match guard.split_once(" from ") {
+                    Some((function, module)) => {
+                        let module = module.trim().trim_end_matches(';');
+                        let unquoted = module
+                            .strip_prefix('"')
+                            .and_then(|i| i.strip_suffix('"'))
+                            .or_else(|| module.strip_prefix('\'').and_then(|i| i.strip_suffix('\'')))?;
+                        (function, Some(unquoted.to_string()))
+                    }
+                    None => (guard.trim_end_matches(';'), None),
+                };
+                Some((
+                    name.trim().to_string(),
+                    Import {
+                        name: function.trim().to_string(),
+                        module,
+                    },
+                ))
+            });
+            match parsed {
+                Some(guard) if !guard.0.is_empty() && !guard.1.name.is_empty() => guard,
+                _ => {
+                    eprintln!(
+                        "Invalid guard on line {}, expected `<TYPE> = <FUNCTION> [from \"<MODULE>\"]`: {}",
+                        i + 1,
+                        line
+                    );
+                    process::exit(1);
+                }
+            }
+        })
+        .collect()
+}
+
+/// The format implied by the output path, type guards unless it has one of the other formats' extensions.
+pub fn get_format(file_path: &str) -> Format {
+    if file_path.ends_with(".zod.ts") || file_path.ends_with(".zod.js") {
+        Format::Zod
+    } else if file_path.ends_with(".schema.json") {
+        Format::JsonSchema
+    } else if file_path.ends_with(".rs") {
+        Format::Rust
+    } else if [".openapi.json", ".openapi.yaml", ".openapi.yml"]
+        .iter()
+        .any(|i| file_path.ends_with(i))
+    {
+        Format::OpenApi
+    } else if file_path.ends_with(".py") {
+        Format::TypedDict
+    } else {
+        Format::Guards
+    }
+}
+
+/// The module specifier to import `to` from the file `from`, e.g. `../types` for `out/guards.js` and `types.ts`.
+pub fn relative_module(from: &str, to: &str) -> String {
+    let mut module = relative_path(from, to);
+    for extension in [".d.ts", ".tsx", ".ts"] {
+        if let Some(stripped) = module.strip_suffix(extension) {
+            module = stripped.to_string();
+            break;
+        }
+    }
+    if module.starts_with("..") {
+        module
+    } else {
+        format!("./{module}")
+    }
+}
+
+/// The path of `to` relative to the directory of the file `from`, e.g. `../types.ts` for `out/guards.js` and `types.ts`.
+pub fn relative_path(from: &str, to: &str) -> String {
+    let absolute = |path: &str| -> Vec<String> {
+        let mut parts: Vec<String> = Vec::new();
+        for part in env::current_dir().unwrap_or_default().join(path).components() {
+            match part {
+                Component::ParentDir => {
+                    parts.pop();
+                }
+                Component::CurDir => (),
+                part => parts.push(part.as_os_str().to_string_lossy().to_string()),
+            }
+        }
+        parts
+    };
+    let (from, to) = (absolute(from), absolute(to));
+    let from_dir = &from[..from.len().saturating_sub(1)];
+    let common = from_dir.iter().zip(&to).take_while(|(a, b)| a == b).count();
+    let mut module: Vec<String> = vec!["..".to_string(); from_dir.len() - common];
+    module.extend(to[common..].iter().cloned());
+    module.join("/")
+}
+
+pub fn get_extension(file_path: String) -> Extension {
+    if file_path.ends_with(".js") {
+        Extension::JS
+    } else if file_path.ends_with(".d.ts") {
+        Extension::DTS
+    } else if file_path.ends_with(".ts") {
+        Extension::TS
+    } else {
+        panic!("File extension is not .ts or .js");
+    }
+}
+
+pub const USAGE: &str = "USAGE: ts-runtime-typechecker <READ-FILE-PATH> <WRITE-FILE-PATH> [OPTIONS]
+
+A <WRITE-FILE-PATH> ending with / or naming a directory gets one TypeScript module per guard and an index.ts.
+
+OPTIONS:
+    --format=<guards|zod|json-schema|rust|openapi|typeddict|pydantic>
+                                   Generate type guards (the default), zod schemas, a JSON Schema, serde types,
+                                   OpenAPI components, Python `TypedDict`s or pydantic models. Otherwise chosen by a
+                                   `.zod.ts`, `.zod.js`, `.schema.json`, `.rs`, `.openapi.<json|yaml|yml>` or `.py`
+                                   <WRITE-FILE-PATH>
+    --validators                   Also generate `validate<Name>` functions that report the path of every failing check
+    --assertions                   Also generate `assert<Name>` functions that throw a `TypeError` (implies --validators)
+    --assert-error=<CLASS>[:<MODULE>]
+                                   Error class thrown by `assert<Name>`, imported from <MODULE> if given
+    --parsers                      Also generate `parse<Name>` and `safeParse<Name>` JSON helpers (implies --validators)
+    --revive                       Make the JSON helpers convert `bigint` and `Date` fields (implies --parsers)
+    --module=<esm|commonjs|global=<NAME>>
+                                   Export the functions of a .js file as an ES module (the default), as CommonJS
+                                   `exports`, or as properties of `globalThis.<NAME>` from a plain script
+    --declarations                 Also write a `.d.ts` file declaring the functions of the .js output
+    --minify                       Keep each function on one line instead of pretty printing the guards
+    --print-width=<N>              Line width the pretty printer breaks lines at, 120 by default
+    --tab-width=<N>                Spaces per indentation level, 4 by default
+    --use-tabs                     Indent with tabs
+    --single-quote                 Prefer single quotes for strings
+    --no-semi                      Leave out semicolons where they aren't needed
+    --source-map                   Also write a <OUTPUT>.map source map from each property check to its declaration
+    --emit-tests                   Also write a <OUTPUT>.test.ts file that runs the guards on made up valid values and
+                                   on invalid values mutated from them, with `node:test`
+    --guard-name=<TEMPLATE>        Name of the guards, `is{Name}` by default. `{Name}` is the interface name, `{name}`
+                                   the same with its first word in lower case and `{snake_name}` it in snake case
+    --validator-name=<TEMPLATE>    Name of the validators, `validate{Name}` by default
+    --assertion-name=<TEMPLATE>    Name of the assertion functions, `assert{Name}` by default
+    --parser-name=<TEMPLATE>       Name of the JSON parsers, `parse{Name}` by default
+    --safe-parser-name=<TEMPLATE>  Name of the JSON parsers that return a result, `safeParse{Name}` by default
+    --inherited-keys               Check keys with `in`, accepting properties inherited from the prototype chain,
+                                   instead of `Object.prototype.hasOwnProperty.call`
+    --plain-objects                Only accept objects whose prototype is `Object.prototype` or `null`
+    --brand=<BRAND>=<FUNCTION>[:<MODULE>]
+                                   Also check values of branded type <BRAND> with <FUNCTION>, imported from <MODULE>
+                                   if given
+    --guards=<FILE>                Guards for types that aren't declared in the input, one
+                                   `<TYPE> = <FUNCTION> [from \"<MODULE>\"]` per line";
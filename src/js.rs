@@ -1,363 +1,1034 @@
-use rayon::prelude::{IndexedParallelIterator, IntoParallelIterator, IntoParallelRefIterator, ParallelIterator};
-
-use crate::{
-    cmd::Extension,
-    lexer::Type,
-    parsers::{Entry, Generic, Key, Value},
-};
-
-#[derive(Debug, Clone, PartialEq)]
-#[allow(unused)]
-pub enum JSToken {
-    String(String),
-    Id(String),
-    Addr(Addr),
-    Number(String),
-    ArrayIsArray(Addr),
-    ObjectKeysLength(Addr),
-    AddrLength(Addr),
-    JSType(JSType),
-    EqEq,
-    EqEqEq,
-    NotEq,
-    NotEqEq,
-    In,
-    And,
-    Or,
-    LPar,
-    RPar,
-    Typeof,
-    True,
-    False,
-    Undefined,
-    Null,
-    None,
-}
-
-#[derive(Debug, Clone, PartialEq)]
-pub enum JSType {
-    String,
-    Number,
-    Function,
-    Boolean,
-    Object,
-    Undefined,
-    Symbol,
-    BigInt,
-}
-
-type Addr = Vec<String>;
-
-pub fn interfaces_to_js_string(interfaces: Vec<Entry>, write_path_extension: Extension) -> String {
-    interfaces
-        .into_par_iter()
-        .map(|i| {
-            let entries_len = i.value.len();
-            let interface_name = if let Key::Name(name) = i.key {
-                name
-            } else {
-                panic!("Name of interface not found");
-            };
-            let string = i
-                .value
-                .into_par_iter()
-                .map(|j| {
-                    let all = to_js_token(j, vec!["o".to_string()]);
-                    js_tokens_to_string(all)
-                })
-                .collect::<Vec<String>>()
-                .join("");
-            let return_body = return_body(entries_len, string);
-            format!("{}\n", function_dec(interface_name, return_body, write_path_extension))
-        })
-        .collect::<Vec<String>>()
-        .join("\n")
-}
-
-pub fn to_js_token(value: Value, addr: Vec<String>) -> Vec<JSToken> {
-    match value {
-        Value::Entry(e) => match e.key {
-            Key::Name(n) => {
-                let new_addr = [addr.clone(), vec![n.clone()]].concat();
-                let token_vec: Vec<JSToken> = e
-                    .value
-                    .par_iter()
-                    .map(|val| to_js_token(val.clone(), new_addr.clone()))
-                    .flatten()
-                    .collect();
-                let res = [
-                    vec![JSToken::And],
-                    vec![JSToken::String(n), JSToken::In, JSToken::Addr(addr), JSToken::And],
-                    token_vec.clone(),
-                ]
-                .concat();
-                return res;
-            }
-            Key::Optional(n) => {
-                let new_addr = [addr.clone(), vec![n.clone()]].concat();
-                let token_vec: Vec<JSToken> = e
-                    .value
-                    .par_iter()
-                    .map(|val| to_js_token(val.clone(), new_addr.clone()))
-                    .flatten()
-                    .collect();
-                let res = [
-                    vec![JSToken::And],
-                    vec![JSToken::LPar],
-                    vec![
-                        JSToken::String(n.clone()),
-                        JSToken::In,
-                        JSToken::Addr(addr.clone()),
-                        JSToken::EqEqEq,
-                        JSToken::False,
-                    ],
-                    vec![JSToken::Or],
-                    vec![JSToken::String(n), JSToken::In, JSToken::Addr(addr), JSToken::And],
-                    token_vec.clone(),
-                    vec![JSToken::RPar],
-                ]
-                .concat();
-                return res;
-            }
-            Key::Generic(g) => match g {
-                Generic::Custom(_) => vec![JSToken::None],
-                Generic::Array => {
-                    let new_addr = [addr.clone(), vec!["0".to_string()]].concat();
-                    let token_vec: Vec<JSToken> = e
-                        .value
-                        .par_iter()
-                        .map(|val| to_js_token(val.clone(), new_addr.clone()))
-                        .flatten()
-                        .collect();
-                    let res = [
-                        vec![JSToken::LPar, JSToken::ArrayIsArray(addr.clone()), JSToken::And],
-                        token_vec,
-                        vec![JSToken::RPar],
-                    ]
-                    .concat();
-                    return res;
-                }
-            },
-            Key::Or => {
-                let l = to_js_token(e.value[0].clone(), addr.clone());
-                let r = to_js_token(e.value[1].clone(), addr.clone());
-                let res = [vec![JSToken::LPar], l, vec![JSToken::Or], r, vec![JSToken::RPar]].concat();
-                return res;
-            }
-            Key::And => {
-                let l = to_js_token(e.value[0].clone(), addr.clone());
-                let r = to_js_token(e.value[1].clone(), addr.clone());
-                let res = [vec![JSToken::LPar], l, vec![JSToken::And], r, vec![JSToken::RPar]].concat();
-                return res;
-            }
-            Key::None => {
-                let entries_len = e.value.len();
-                let token_vec: Vec<JSToken> = e
-                    .value
-                    .par_iter()
-                    .map(|val| to_js_token(val.clone(), addr.clone()))
-                    .flatten()
-                    .collect();
-                let res = [
-                    vec![JSToken::LPar],
-                    typeof_token(addr.clone(), JSType::Object),
-                    vec![JSToken::And],
-                    loose_not_eq(JSToken::Addr(addr.clone()), JSToken::Null),
-                    vec![JSToken::And],
-                    strict_eq(
-                        JSToken::ObjectKeysLength(addr.clone()),
-                        JSToken::Number(entries_len.to_string()),
-                    ),
-                    token_vec,
-                    vec![JSToken::RPar],
-                ]
-                .concat();
-                return res;
-            }
-            Key::Paren => {
-                let token_vec = e
-                    .value
-                    .par_iter()
-                    .map(|val| to_js_token(val.clone(), addr.clone()))
-                    .flatten()
-                    .collect();
-
-                // let res = [vec![JSToken::LPar], token_vec, vec![JSToken::RPar]].concat();
-                // return res;
-                return token_vec;
-            }
-            Key::Tuple => {
-                let mut token_vec: Vec<JSToken> = e
-                    .value
-                    .par_iter()
-                    .enumerate()
-                    .map(|i| {
-                        vec![
-                            to_js_token(i.1.clone(), [addr.clone(), vec![i.0.to_string()]].concat()),
-                            vec![JSToken::And],
-                        ]
-                    })
-                    .flatten()
-                    .flatten()
-                    .collect();
-
-                token_vec.pop(); // remove last And
-
-                let res = [
-                    vec![JSToken::LPar, JSToken::ArrayIsArray(addr.clone()), JSToken::And],
-                    strict_eq(
-                        JSToken::AddrLength(addr.clone()),
-                        JSToken::Number(e.value.len().to_string()),
-                    ),
-                    vec![JSToken::And],
-                    token_vec,
-                    vec![JSToken::RPar],
-                ]
-                .concat();
-                return res;
-            }
-        },
-        Value::Type(Type::Number) => return typeof_token(addr, JSType::Number),
-        Value::Type(Type::String) => return typeof_token(addr, JSType::String),
-        Value::Type(Type::Object) => return typeof_token(addr, JSType::Object),
-        Value::Type(Type::Boolean) => return typeof_token(addr, JSType::Boolean),
-        Value::Type(Type::Undefined) => return typeof_token(addr, JSType::Undefined),
-        Value::Type(Type::Function) => return typeof_token(addr, JSType::Function),
-        Value::Type(Type::Symbol) => return typeof_token(addr, JSType::Symbol),
-        Value::Type(Type::BigInt) => return typeof_token(addr, JSType::BigInt),
-        Value::Type(Type::False) => return strict_eq(JSToken::Addr(addr), JSToken::False),
-        Value::Type(Type::True) => return strict_eq(JSToken::Addr(addr), JSToken::True),
-        Value::Type(Type::Null) => return strict_eq(JSToken::Addr(addr), JSToken::Null),
-        Value::Type(Type::StringLit(str)) => return strict_eq(JSToken::Addr(addr), JSToken::String(str)),
-        Value::Type(Type::NumberLit(str)) => return strict_eq(JSToken::Addr(addr), JSToken::Number(str)),
-        Value::Type(Type::Custom(_)) => typeof_token(addr, JSType::Object),
-        Value::Type(Type::Any) | Value::Type(Type::Unknown) => {
-            return [
-                vec![JSToken::LPar],
-                typeof_token(addr.clone(), JSType::Object),
-                vec![JSToken::Or],
-                typeof_token(addr.clone(), JSType::Undefined),
-                vec![JSToken::Or],
-                typeof_token(addr.clone(), JSType::String),
-                vec![JSToken::Or],
-                typeof_token(addr.clone(), JSType::Number),
-                vec![JSToken::Or],
-                typeof_token(addr.clone(), JSType::Boolean),
-                vec![JSToken::Or],
-                typeof_token(addr.clone(), JSType::Symbol),
-                vec![JSToken::Or],
-                typeof_token(addr.clone(), JSType::BigInt),
-                vec![JSToken::Or],
-                typeof_token(addr.clone(), JSType::Function),
-                vec![JSToken::RPar],
-            ]
-            .concat()
-        }
-        _ => vec![JSToken::None],
-    }
-}
-
-fn typeof_token(addr: Addr, js_type: JSType) -> Vec<JSToken> {
-    vec![
-        JSToken::Typeof,
-        JSToken::Addr(addr),
-        JSToken::EqEqEq,
-        JSToken::JSType(js_type),
-    ]
-}
-
-fn strict_eq(left: JSToken, right: JSToken) -> Vec<JSToken> {
-    vec![left, JSToken::EqEqEq, right]
-}
-
-fn loose_not_eq(left: JSToken, right: JSToken) -> Vec<JSToken> {
-    vec![left, JSToken::NotEq, right]
-}
-
-pub fn js_tokens_to_string(tokens: Vec<JSToken>) -> String {
-    tokens
-        .par_iter()
-        .map(|i| {
-            match i {
-                JSToken::String(s) => {
-                    format!("\"{}\"", escape_double_q(s.clone()))
-                }
-                JSToken::Addr(addr) => addr_to_string(addr.clone()),
-                JSToken::Id(id) => id.clone(),
-                JSToken::Number(n) => n.clone(),
-                JSToken::EqEq => "==".to_string(),
-                JSToken::EqEqEq => "===".to_string(),
-                JSToken::NotEq => "!=".to_string(),
-                JSToken::NotEqEq => "!==".to_string(),
-                JSToken::In => "in ".to_string(),
-                JSToken::And => "&&".to_string(),
-                JSToken::Or => "||".to_string(),
-                JSToken::LPar => "(".to_string(),
-                JSToken::RPar => ")".to_string(),
-                JSToken::Typeof => "typeof ".to_string(),
-                JSToken::True => "true".to_string(),
-                JSToken::False => "false".to_string(),
-                JSToken::Undefined => "undefined".to_string(),
-                JSToken::Null => "null".to_string(),
-                JSToken::None => "".to_string(),
-                JSToken::ArrayIsArray(addr) => format!("Array.isArray({})", addr_to_string(addr.clone())),
-                JSToken::AddrLength(addr) => format!("{}.length", addr_to_string(addr.clone())),
-                JSToken::ObjectKeysLength(addr) => format!("Object.keys({}).length", addr_to_string(addr.clone())),
-                JSToken::JSType(t) => String::from(match t {
-                    JSType::String => "\"string\"",
-                    JSType::Number => "\"number\"",
-                    JSType::Function => "\"function\"",
-                    JSType::Boolean => "\"boolean\"",
-                    JSType::Object => "\"object\"",
-                    JSType::Undefined => "\"undefined\"",
-                    JSType::Symbol => "\"symbol\"",
-                    JSType::BigInt => "\"bigint\"",
-                }),
-                // _ => "".to_string(),
-            }
-        })
-        .collect::<Vec<String>>()
-        .join("")
-}
-
-fn addr_to_string(addr: Addr) -> String {
-    let mut temp = vec![format!("{}", addr[0].clone())];
-    for i in 1..addr.len() {
-        temp.push(format!("[{}]", format!("\"{}\"", addr[i])));
-    }
-    return temp.join("");
-}
-
-pub fn function_dec(name: String, return_body: String, extension: Extension) -> String {
-    match extension {
-        Extension::JS => format!("export function is{name}(o){{return({return_body})}}"),
-        Extension::TS | Extension::DTS => {
-            format!("export function is{name}(o: unknown): o is {name}{{return({return_body})}}")
-        }
-    }
-}
-
-pub fn return_body(entries_len: usize, return_body: String) -> String {
-    format!("o!=null&&typeof o===\"object\"&&Object.keys(o).length==={entries_len}{return_body}")
-}
-
-fn escape_double_q(string: String) -> String {
-    let mut string = Vec::from(string);
-    let mut i = 0;
-    while i < string.len() {
-        let c = string[i] as char;
-        if c == '"' {
-            if i == 0 {
-                string.insert(i, b'\\');
-                i += 1;
-            } else {
-                if string[i - 1] != b'\\' {
-                    string.insert(i, b'\\');
-                    i += 1;
-                }
-            }
-        }
-        i += 1;
-    }
-    String::from_utf8(string).unwrap()
-}
+use rayon::prelude::{IndexedParallelIterator, IntoParallelIterator, IntoParallelRefIterator, ParallelIterator};
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::{
+    cmd::{relative_module, relative_path, Extension, Import, Module, Naming, Options},
+    jsdoc::{Constraint, Doc, Format},
+    json_schema::json_string,
+    lexer::Type,
+    optimize::optimize,
+    parsers::{
+        brand, discriminate, or_branches, unparen, value_to_ts_string, Brand, Discriminated, Entry, Generic, Key, Span,
+        Value,
+    },
+    pretty::{pretty_print, pretty_print_marked, strip_markers},
+    source_map::{self, Mark},
+    validate::{assertion_dec, parse_helpers_dec, to_js_reviver, to_js_validator, validation_helpers, validator_dec},
+};
+
+#[derive(Debug, Clone, PartialEq)]
+#[allow(unused)]
+pub enum JSToken {
+    String(String),
+    Id(String),
+    Addr(Addr),
+    Number(String),
+    ArrayIsArray(Addr),
+    PlainObject(Addr),
+    ObjectKeysLength(Addr),
+    AddrLength(Addr),
+    HasOwn(Addr, String),
+    /// Where the check that follows was declared, see `source_map`.
+    Span(Span),
+    Refine(Addr, Constraint),
+    Call(String, Addr),
+    /// A call of the guard of a generic interface, followed by the guards for its type arguments.
+    Apply(String, Addr, Vec<String>),
+    Switch(Addr, Vec<(Vec<JSToken>, Vec<JSToken>)>),
+    JSType(JSType),
+    EqEq,
+    EqEqEq,
+    NotEq,
+    NotEqEq,
+    In,
+    And,
+    Or,
+    LPar,
+    RPar,
+    Typeof,
+    Not,
+    True,
+    False,
+    Undefined,
+    Null,
+    None,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum JSType {
+    String,
+    Number,
+    Function,
+    Boolean,
+    Object,
+    Undefined,
+    Symbol,
+    BigInt,
+}
+
+pub type Addr = Vec<String>;
+
+/// The guards for `interfaces`, declared in `source`, in a module with an `@generated` banner.
+pub fn interfaces_to_js_string(interfaces: Vec<Entry>, source: &str, options: &Options) -> String {
+    interfaces_to_js_marked(interfaces, source, options).0
+}
+
+/// `interfaces_to_js_string`, also returning where the check of each property ended up with `--source-map`.
+pub fn interfaces_to_js_marked(interfaces: Vec<Entry>, source: &str, options: &Options) -> (String, Vec<Mark>) {
+    let extension = options.extension;
+    let names: Vec<String> = interfaces
+        .iter()
+        .filter_map(|i| match &i.key {
+            Key::Name(name) => Some(name.clone()),
+            _ => None,
+        })
+        .collect();
+    check_names(&names, options);
+    let functions = interfaces
+        .into_par_iter()
+        .map(|i| interface_functions(i, source, options))
+        .collect::<Vec<String>>()
+        .join("\n");
+    let module = if let Extension::DTS = extension {
+        declaration_module(&names, functions, options)
+    } else {
+        module_string(functions, options)
+    };
+    generated_file_marked(module, options)
+}
+
+/// The guards for `interfaces` in the directory `options.write_path`, one module per interface named after its guard,
+/// plus an `index.ts` re-exporting them all. Returns the path and content of every file. The modules import the
+/// guards they call and the types from the input file.
+pub fn interfaces_to_js_files(interfaces: Vec<Entry>, source: &str, options: &Options) -> Vec<(String, String)> {
+    let names: Vec<String> = interfaces
+        .iter()
+        .filter_map(|i| match &i.key {
+            Key::Name(name) => Some(name.clone()),
+            _ => None,
+        })
+        .collect();
+    check_names(&names, options);
+    let path = |stem: &str| {
+        Path::new(&options.write_path)
+            .join(format!("{stem}.ts"))
+            .to_string_lossy()
+            .to_string()
+    };
+    let file = |path: String, module: String| {
+        let options = Options {
+            write_path: path.clone(),
+            ..options.clone()
+        };
+        (path, generated_file(module, &options))
+    };
+    let mut stems: HashMap<String, String> = HashMap::new();
+    for stem in names
+        .iter()
+        .map(|n| options.naming.guard(n))
+        .chain(["index".to_string(), "validation".to_string()])
+    {
+        if let Some(other) = stems.insert(stem.to_lowercase(), stem.clone()) {
+            panic!("{other}.ts and {stem}.ts would be the same file on case-insensitive file systems");
+        }
+    }
+    let mut files: Vec<(String, String)> = interfaces
+        .into_par_iter()
+        .map(|i| {
+            let name = match &i.key {
+                Key::Name(name) => name.clone(),
+                _ => panic!("Name of interface not found"),
+            };
+            let guard = options.naming.guard(&name);
+            let options = Options {
+                write_path: path(&guard),
+                ..options.clone()
+            };
+            let mut used: Vec<Import> = options.assertions.iter().cloned().collect();
+            imported_functions(&i.value, &name, &options, &mut used);
+            let functions = interface_functions(i, source, &options);
+            let mut imports = format!(
+                "import type {{ {name} }} from {};\n",
+                js_string(&relative_module(&options.write_path, &options.read_path))
+            );
+            if options.validators {
+                imports.push_str(
+                    "import { _received, type ValidationError, type ValidationResult } from \"./validation\";\n",
+                );
+            }
+            let module = format!("{imports}{}\n{functions}", imports_dec(used.iter().collect()));
+            (options.write_path.clone(), generated_file(module, &options))
+        })
+        .collect();
+    let mut index: String = names
+        .iter()
+        .map(|n| format!("export * from \"./{}\";\n", options.naming.guard(n)))
+        .collect();
+    if options.validators {
+        index.push_str("export type { ValidationError, ValidationResult } from \"./validation\";\n");
+        let helpers = validation_helpers(Extension::TS).replace("function _received", "export function _received");
+        files.push(file(path("validation"), helpers));
+    }
+    files.push(file(path("index"), index));
+    files
+}
+
+/// The functions from other modules that the checks of `values` call: the guards of other interfaces referenced
+/// recursively, which are called rather than inlined, the `--guards` of custom types and the `--brand` checks. The
+/// guard of `name` itself is declared in the module.
+fn imported_functions(values: &[Value], name: &str, options: &Options, used: &mut Vec<Import>) {
+    for value in values {
+        match value {
+            Value::Entry(e) => match &e.key {
+                Key::Ref(n) if e.value.is_empty() => {
+                    if n != name {
+                        used.extend(referenced_functions(n, options));
+                    }
+                }
+                Key::Generic(Generic::Ref(n)) => {
+                    if n != name {
+                        used.extend(referenced_functions(n, options));
+                    }
+                    imported_functions(&e.value, name, options, used);
+                }
+                Key::And => match brand(e) {
+                    Some(b) => used.extend(brand_check(&b, options).cloned()),
+                    None => imported_functions(&e.value, name, options, used),
+                },
+                _ => imported_functions(&e.value, name, options, used),
+            },
+            Value::Type(Type::Custom(n)) => {
+                used.extend(options.guards.iter().filter(|(i, _)| i == n).map(|(_, f)| f.clone()))
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The guard of the interface `name`, and its validator if the checks of recursive references call validators too.
+fn referenced_functions(name: &str, options: &Options) -> Vec<Import> {
+    let guard = options.naming.guard(name);
+    let mut functions = vec![Import {
+        module: Some(format!("./{guard}")),
+        name: guard.clone(),
+    }];
+    if options.validators {
+        functions.push(Import {
+            module: Some(format!("./{guard}")),
+            name: options.naming.validator(name),
+        });
+    }
+    functions
+}
+
+/// Puts the `@generated` banner on top of `module`, and pretty prints it unless minifying.
+pub fn generated_file(module: String, options: &Options) -> String {
+    generated_file_marked(module, options).0
+}
+
+/// `generated_file`, also returning where the source map markers in `module` ended up. They are left out of the code.
+fn generated_file_marked(module: String, options: &Options) -> (String, Vec<Mark>) {
+    let string = format!(
+        "/**\n * @generated by ts-runtime-typechecker from {}, do not edit.\n */\n\n{module}",
+        relative_path(&options.write_path, &options.read_path)
+    );
+    match (options.style.minify, options.source_map) {
+        (true, true) => strip_markers(&string),
+        (true, false) => (string, Vec::new()),
+        (false, true) => pretty_print_marked(&string, &options.style),
+        (false, false) => (pretty_print(&string, &options.style), Vec::new()),
+    }
+}
+
+/// The guard of the interface `i`, with its doc comment, and its validator, assertion and parsers if enabled.
+fn interface_functions(i: Entry, source: &str, options: &Options) -> String {
+    let extension = options.extension;
+    let entries_len = i.value.len();
+    let params = i.params.clone();
+    let interface_name = if let Key::Name(name) = i.key {
+        name
+    } else {
+        panic!("Name of interface not found");
+    };
+    let validator = if options.validators {
+        let object = Value::Entry(Entry {
+            key: Key::None,
+            value: i.value.clone(),
+            doc: None,
+            params: Vec::new(),
+            span: i.span,
+        });
+        let body = to_js_validator(&object, vec!["o".to_string()], "e", 0, options);
+        let mut string = format!(
+            "\n{}\n",
+            validator_dec(&interface_name, &params, body, extension, &options.naming)
+        );
+        if let Some(error) = &options.assertions {
+            string.push_str(&format!(
+                "\n{}\n",
+                assertion_dec(&interface_name, &params, &error.name, extension, &options.naming)
+            ));
+        }
+        if options.parsers {
+            let error_class = options.assertions.as_ref().map_or("TypeError", |i| i.name.as_str());
+            let revive = if options.revive {
+                Some(to_js_reviver(&object, "o", 0)).filter(|i| !i.is_empty())
+            } else {
+                None
+            };
+            string.push_str(&format!(
+                "\n{}\n",
+                parse_helpers_dec(
+                    &interface_name,
+                    &params,
+                    error_class,
+                    revive,
+                    extension,
+                    &options.naming
+                )
+            ));
+        }
+        string
+    } else {
+        String::new()
+    };
+    let tokens = i
+        .value
+        .into_par_iter()
+        .map(|j| to_js_token(j, vec!["o".to_string()], options))
+        .flatten()
+        .collect();
+    let return_body = js_tokens_to_string(optimize(return_body(entries_len, tokens, options)));
+    format!(
+        "{}\n{}\n{}",
+        guard_doc(&interface_name, &i.doc, i.span, source, options),
+        function_dec(interface_name, &params, return_body, extension, &options.naming),
+        validator
+    )
+}
+
+/// Panics if two generated functions would get the same name, e.g. `--guard-name={name}` for interfaces `Foo` and
+/// `foo`, or the same template for guards and validators.
+fn check_names(names: &[String], options: &Options) {
+    let mut seen: HashMap<String, String> = HashMap::new();
+    if options.validators {
+        seen.insert("_received".to_string(), "the helper of the validators".to_string());
+    }
+    for name in names {
+        let mut functions = vec![(options.naming.guard(name), "guard")];
+        if options.validators {
+            functions.push((options.naming.validator(name), "validator"));
+        }
+        if options.assertions.is_some() {
+            functions.push((options.naming.assertion(name), "assertion"));
+        }
+        if options.parsers {
+            functions.push((options.naming.parser(name), "parser"));
+            functions.push((options.naming.safe_parser(name), "safe parser"));
+        }
+        for (function, kind) in functions {
+            let this = format!("the {kind} of {name}");
+            if let Some(other) = seen.insert(function.clone(), this.clone()) {
+                panic!("Two generated functions would be named {function}: {other} and {this}");
+            }
+        }
+    }
+}
+
+/// The doc comment of the guard for the interface `name`: the JSDoc of the interface, followed by where it is declared
+/// and its tags.
+fn guard_doc(name: &str, doc: &Option<Doc>, span: Span, source: &str, options: &Options) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    if let Some(doc) = doc.as_ref().filter(|d| !d.description.is_empty()) {
+        lines.extend(doc.description.lines().map(str::to_string));
+        lines.push(String::new());
+    }
+    let file = relative_path(&options.write_path, &options.read_path);
+    lines.push(match span.0.and_then(|offset| source.get(..offset)) {
+        Some(before) => format!(
+            "Type guard for `{name}`, declared at {file}:{}.",
+            before.matches('\n').count() + 1
+        ),
+        None => format!("Type guard for `{name}`, declared in {file}."),
+    });
+    for tag in doc.iter().flat_map(|d| &d.tags) {
+        lines.push(format!("@{} {}", tag.name, tag.value).trim_end().to_string());
+    }
+    let lines: String = lines
+        .iter()
+        .map(|l| match l.is_empty() {
+            true => " *\n".to_string(),
+            false => format!(" * {}\n", l.replace("*/", "*\\/")),
+        })
+        .collect();
+    format!("/**\n{lines} */")
+}
+
+fn module_string(functions: String, options: &Options) -> String {
+    let extension = options.extension;
+    let imports = options
+        .assertions
+        .iter()
+        .chain(options.brands.iter().map(|(_, f)| f))
+        .chain(options.guards.iter().map(|(_, f)| f))
+        .collect();
+    let body = if options.validators {
+        format!("{}\n{}", validation_helpers(extension), functions)
+    } else {
+        functions
+    };
+    module_dec(imports, body, &options.module)
+}
+
+/// A declaration file imports the interfaces it refers to from the input. For the global format the functions are
+/// declared in a global namespace instead of being exported.
+fn declaration_module(names: &[String], functions: String, options: &Options) -> String {
+    let body = if options.validators {
+        format!("{}\n{}", validation_helpers(Extension::DTS), functions)
+    } else {
+        functions
+    };
+    let types = format!(
+        "import type {{ {} }} from {};\n",
+        names.join(", "),
+        js_string(&relative_module(&options.write_path, &options.read_path))
+    );
+    match &options.module {
+        Module::Global(namespace) => format!(
+            "{types}declare global {{\nnamespace {namespace} {{\n{}\n}}\n}}\n",
+            body.replace("export declare function ", "function ")
+        ),
+        _ => format!("{types}{body}\n"),
+    }
+}
+
+/// Wraps the `export function` declarations of `body` in the module format. CommonJS assigns them to `exports` and
+/// the global format to a namespace object on `globalThis`, in which case nothing can be imported.
+pub fn module_dec(imports: Vec<&Import>, body: String, module: &Module) -> String {
+    let exported = || -> Vec<&str> {
+        body.match_indices("export function ")
+            .map(|(i, m)| {
+                let name = &body[i + m.len()..];
+                &name[..name.find(['(', '<']).unwrap_or(name.len())]
+            })
+            .collect()
+    };
+    match module {
+        Module::Esm => format!("{}{}", imports_dec(imports), body),
+        Module::CommonJs => format!(
+            "\"use strict\";\n{}{}\n{}",
+            requires_dec(imports),
+            body.replace("export function ", "function "),
+            exported()
+                .iter()
+                .map(|i| format!("exports.{i}={i};\n"))
+                .collect::<String>()
+        ),
+        Module::Global(namespace) => {
+            let ns = format!("globalThis[{}]", js_string(namespace));
+            format!(
+                "(function(ns){{\"use strict\";\n{}\n{}}})({ns}={ns}||{{}});\n",
+                body.replace("export function ", "function "),
+                exported().iter().map(|i| format!("ns.{i}={i};\n")).collect::<String>()
+            )
+        }
+    }
+}
+
+/// `imports_dec` for CommonJS: one `const { a, b } = require("module");` line per module.
+fn requires_dec(imports: Vec<&Import>) -> String {
+    imports_dec(imports)
+        .lines()
+        .map(|i| {
+            let (names, module) = i["import ".len()..].trim_end_matches(';').split_once(" from ").unwrap();
+            format!("const {names} = require({module});\n")
+        })
+        .collect()
+}
+
+/// `string` as a double-quoted JavaScript string, escaped like JSON plus the line and paragraph separators.
+pub fn js_string(string: &str) -> String {
+    json_string(string)
+        .replace('\u{2028}', "\\u2028")
+        .replace('\u{2029}', "\\u2029")
+}
+
+/// A string literal or quoted key as written in the input, double-quoted with its escapes kept.
+pub fn source_string(string: &str) -> String {
+    format!("\"{}\"", escape_double_q(string.to_string()))
+}
+
+/// One `import { a, b } from "module";` line per module, in order of first use. Globals need no import.
+pub fn imports_dec(imports: Vec<&Import>) -> String {
+    let mut modules: Vec<(&String, Vec<&String>)> = Vec::new();
+    for import in imports {
+        if let Some(module) = &import.module {
+            match modules.iter_mut().find(|(m, _)| *m == module) {
+                Some((_, names)) if names.contains(&&import.name) => (),
+                Some((_, names)) => names.push(&import.name),
+                None => modules.push((module, vec![&import.name])),
+            }
+        }
+    }
+    modules
+        .iter()
+        .map(|(module, names)| {
+            format!(
+                "import {{ {} }} from \"{}\";\n",
+                names.iter().map(|i| i.as_str()).collect::<Vec<&str>>().join(", "),
+                escape_double_q(module.to_string())
+            )
+        })
+        .collect()
+}
+
+pub fn to_js_token(value: Value, addr: Vec<String>, options: &Options) -> Vec<JSToken> {
+    match value {
+        Value::Entry(e) => match e.key {
+            Key::Name(n) => {
+                let new_addr = [addr.clone(), vec![n.clone()]].concat();
+                let token_vec: Vec<JSToken> = e
+                    .value
+                    .par_iter()
+                    .map(|val| to_js_token(val.clone(), new_addr.clone(), options))
+                    .flatten()
+                    .collect();
+
+                [
+                    vec![JSToken::And],
+                    has_key(addr, n, e.span, options),
+                    vec![JSToken::And],
+                    token_vec.clone(),
+                    refinements(&e.doc, &new_addr),
+                ]
+                .concat()
+            }
+            Key::Optional(n) => {
+                let new_addr = [addr.clone(), vec![n.clone()]].concat();
+                let token_vec: Vec<JSToken> = e
+                    .value
+                    .par_iter()
+                    .map(|val| to_js_token(val.clone(), new_addr.clone(), options))
+                    .flatten()
+                    .collect();
+
+                [
+                    vec![JSToken::And],
+                    vec![JSToken::LPar],
+                    has_key(addr.clone(), n.clone(), e.span, options),
+                    vec![JSToken::EqEqEq, JSToken::False],
+                    vec![JSToken::Or],
+                    has_key(addr, n, e.span, options),
+                    vec![JSToken::And],
+                    token_vec.clone(),
+                    refinements(&e.doc, &new_addr),
+                    vec![JSToken::RPar],
+                ]
+                .concat()
+            }
+            Key::Generic(g) => match g {
+                Generic::Custom(_) => vec![JSToken::None],
+                Generic::Ref(n) => vec![generic_call(&n, &e.value, addr, options)],
+                Generic::Array => {
+                    let new_addr = [addr.clone(), vec!["0".to_string()]].concat();
+                    let token_vec: Vec<JSToken> = e
+                        .value
+                        .par_iter()
+                        .map(|val| to_js_token(val.clone(), new_addr.clone(), options))
+                        .flatten()
+                        .collect();
+
+                    [
+                        vec![JSToken::LPar, JSToken::ArrayIsArray(addr.clone()), JSToken::And],
+                        token_vec,
+                        vec![JSToken::RPar],
+                    ]
+                    .concat()
+                }
+            },
+            Key::Or => {
+                if let Some(d) = discriminate(or_branches(&e)) {
+                    return discriminated_token(d, addr, options);
+                }
+                let l = to_js_token(e.value[0].clone(), addr.clone(), options);
+                let r = to_js_token(e.value[1].clone(), addr.clone(), options);
+
+                [vec![JSToken::LPar], l, vec![JSToken::Or], r, vec![JSToken::RPar]].concat()
+            }
+            Key::And => {
+                if let Some(b) = brand(&e) {
+                    return brand_token(b, addr, options);
+                }
+                let l = to_js_token(e.value[0].clone(), addr.clone(), options);
+                let r = to_js_token(e.value[1].clone(), addr.clone(), options);
+
+                [vec![JSToken::LPar], l, vec![JSToken::And], r, vec![JSToken::RPar]].concat()
+            }
+            Key::None => [
+                vec![JSToken::LPar],
+                object_check(addr.clone(), options),
+                vec![JSToken::And],
+                object_token(&e.value, None, addr, options),
+                vec![JSToken::RPar],
+            ]
+            .concat(),
+            Key::Ref(n) if e.value.is_empty() => vec![JSToken::Call(options.naming.guard(&n), addr)],
+            Key::Paren | Key::Alias(_) | Key::Ref(_) => {
+                let token_vec = e
+                    .value
+                    .par_iter()
+                    .map(|val| to_js_token(val.clone(), addr.clone(), options))
+                    .flatten()
+                    .collect();
+
+                // let res = [vec![JSToken::LPar], token_vec, vec![JSToken::RPar]].concat();
+                // return res;
+                token_vec
+            }
+            Key::Tuple => {
+                let mut token_vec: Vec<JSToken> = e
+                    .value
+                    .par_iter()
+                    .enumerate()
+                    .map(|i| {
+                        vec![
+                            to_js_token(i.1.clone(), [addr.clone(), vec![i.0.to_string()]].concat(), options),
+                            vec![JSToken::And],
+                        ]
+                    })
+                    .flatten()
+                    .flatten()
+                    .collect();
+
+                token_vec.pop(); // remove last And
+
+                [
+                    vec![JSToken::LPar, JSToken::ArrayIsArray(addr.clone()), JSToken::And],
+                    strict_eq(
+                        JSToken::AddrLength(addr.clone()),
+                        JSToken::Number(e.value.len().to_string()),
+                    ),
+                    vec![JSToken::And],
+                    token_vec,
+                    vec![JSToken::RPar],
+                ]
+                .concat()
+            }
+        },
+        Value::Type(Type::Number) => typeof_token(addr, JSType::Number),
+        Value::Type(Type::String) => typeof_token(addr, JSType::String),
+        Value::Type(Type::Custom(name)) if options.guards.iter().any(|(i, _)| *i == name) => {
+            let (_, guard) = options.guards.iter().find(|(i, _)| *i == name).unwrap();
+            vec![JSToken::Call(guard.name.clone(), addr)]
+        }
+        Value::Type(Type::Object) | Value::Type(Type::Custom(_)) => [
+            typeof_token(addr.clone(), JSType::Object),
+            vec![JSToken::And],
+            loose_not_eq(JSToken::Addr(addr), JSToken::Null),
+        ]
+        .concat(),
+        Value::Type(Type::Boolean) => typeof_token(addr, JSType::Boolean),
+        Value::Type(Type::Undefined) => typeof_token(addr, JSType::Undefined),
+        Value::Type(Type::Function) => typeof_token(addr, JSType::Function),
+        Value::Type(Type::Symbol) => typeof_token(addr, JSType::Symbol),
+        Value::Type(Type::BigInt) => typeof_token(addr, JSType::BigInt),
+        Value::Type(Type::False) => strict_eq(JSToken::Addr(addr), JSToken::False),
+        Value::Type(Type::True) => strict_eq(JSToken::Addr(addr), JSToken::True),
+        Value::Type(Type::Null) => strict_eq(JSToken::Addr(addr), JSToken::Null),
+        Value::Type(Type::StringLit(str)) => strict_eq(JSToken::Addr(addr), JSToken::String(str)),
+        Value::Type(Type::NumberLit(str)) => strict_eq(JSToken::Addr(addr), JSToken::Number(str)),
+        Value::Type(Type::Any) | Value::Type(Type::Unknown) => vec![JSToken::True],
+        Value::Type(Type::Param(p)) => vec![JSToken::Call(options.naming.guard(&p), addr)],
+        _ => vec![JSToken::None],
+    }
+}
+
+/// The key count and properties of an object whose type was already checked. The value of the property `skip` is not
+/// checked, only that it is present.
+fn object_token(properties: &[Value], skip: Option<&str>, addr: Addr, options: &Options) -> Vec<JSToken> {
+    let token_vec: Vec<JSToken> = properties
+        .par_iter()
+        .map(|val| match val {
+            Value::Entry(Entry {
+                key: Key::Name(n),
+                span,
+                ..
+            }) if Some(n.as_str()) == skip => {
+                [vec![JSToken::And], has_key(addr.clone(), n.clone(), *span, options)].concat()
+            }
+            val => to_js_token(val.clone(), addr.clone(), options),
+        })
+        .flatten()
+        .collect();
+    [
+        strict_eq(
+            JSToken::ObjectKeysLength(addr.clone()),
+            JSToken::Number(properties.len().to_string()),
+        ),
+        token_vec,
+    ]
+    .concat()
+}
+
+/// `typeof o==="object"&&o!=null&&!Array.isArray(o)`, and the prototype check with `--plain-objects`.
+fn object_check(addr: Addr, options: &Options) -> Vec<JSToken> {
+    [
+        typeof_token(addr.clone(), JSType::Object),
+        vec![JSToken::And],
+        loose_not_eq(JSToken::Addr(addr.clone()), JSToken::Null),
+        object_shape(addr, options),
+    ]
+    .concat()
+}
+
+/// Only the member selected by the discriminant is checked, and the object check is shared by all of them:
+/// `(typeof o==="object"&&o!=null&&!Array.isArray(o)&&(o["k"]==="a"?..:..))`. The case already matched the
+/// discriminant, so the members only check that it is an own property.
+fn discriminated_token(d: Discriminated, addr: Addr, options: &Options) -> Vec<JSToken> {
+    let cases = d
+        .cases
+        .par_iter()
+        .map(|(labels, branch)| {
+            let Value::Entry(object) = unparen(branch) else {
+                unreachable!("discriminated union members are objects")
+            };
+            (
+                labels.iter().map(literal_token).collect(),
+                optimize(object_token(&object.value, Some(&d.key), addr.clone(), options)),
+            )
+        })
+        .collect();
+    let switch = [
+        vec![JSToken::LPar],
+        object_check(addr.clone(), options),
+        vec![JSToken::And],
+        vec![JSToken::Switch([addr.clone(), vec![d.key]].concat(), cases)],
+        vec![JSToken::RPar],
+    ]
+    .concat();
+    let others: Vec<JSToken> = d
+        .others
+        .into_par_iter()
+        .map(|i| [to_js_token(i, addr.clone(), options), vec![JSToken::Or]].concat())
+        .flatten()
+        .collect();
+    [vec![JSToken::LPar], others, switch, vec![JSToken::RPar]].concat()
+}
+
+/// Only the primitive of a branded type exists at runtime, optionally refined by the function given with `--brand`.
+fn brand_token(b: Brand, addr: Addr, options: &Options) -> Vec<JSToken> {
+    let check = brand_check(&b, options)
+        .map(|f| vec![JSToken::And, JSToken::Call(f.name.clone(), addr.clone())])
+        .unwrap_or_default();
+    [
+        vec![JSToken::LPar],
+        to_js_token(b.base, addr, options),
+        check,
+        vec![JSToken::RPar],
+    ]
+    .concat()
+}
+
+pub fn brand_check<'a>(b: &Brand, options: &'a Options) -> Option<&'a Import> {
+    let name = b.name.as_ref()?;
+    options.brands.iter().find(|(brand, _)| brand == name).map(|(_, f)| f)
+}
+
+pub fn literal_token(literal: &Type) -> JSToken {
+    match literal {
+        Type::StringLit(str) => JSToken::String(str.clone()),
+        Type::NumberLit(str) => JSToken::Number(str.clone()),
+        Type::True => JSToken::True,
+        Type::False => JSToken::False,
+        Type::Null => JSToken::Null,
+        _ => JSToken::None,
+    }
+}
+
+/// Keys that every object inherits from `Object.prototype`, so `"key" in o` says nothing about them.
+const OBJECT_PROTOTYPE_KEYS: [&str; 12] = [
+    "__proto__",
+    "constructor",
+    "hasOwnProperty",
+    "isPrototypeOf",
+    "propertyIsEnumerable",
+    "toString",
+    "toLocaleString",
+    "valueOf",
+    "__defineGetter__",
+    "__defineSetter__",
+    "__lookupGetter__",
+    "__lookupSetter__",
+];
+
+/// `Object.prototype.hasOwnProperty.call(o,"key")`, or `"key" in o` with `--inherited-keys`. Keys of
+/// `Object.prototype` are always checked as own properties. With `--source-map` the check is marked with the `span`
+/// of the property.
+pub fn has_key(addr: Addr, key: String, span: Span, options: &Options) -> Vec<JSToken> {
+    let check = if options.inherited_keys && !OBJECT_PROTOTYPE_KEYS.contains(&key.as_str()) {
+        vec![JSToken::String(key), JSToken::In, JSToken::Addr(addr)]
+    } else {
+        vec![JSToken::HasOwn(addr, key)]
+    };
+    match span {
+        Span(Some(_)) if options.source_map => [vec![JSToken::Span(span)], check].concat(),
+        _ => check,
+    }
+}
+
+/// Checks for the constraint tags in a property's JSDoc, each only applies to values of the matching JS type.
+fn refinements(doc: &Option<Doc>, addr: &Addr) -> Vec<JSToken> {
+    doc.iter()
+        .flat_map(|d| d.constraints())
+        .flat_map(|c| [JSToken::And, JSToken::Refine(addr.clone(), c)])
+        .collect()
+}
+
+/// Interfaces describe plain objects, so arrays with the right keys are rejected. With `--plain-objects` the
+/// prototype must also be `Object.prototype` or `null`, which rules out class instances, `Map`s and the like.
+fn object_shape(addr: Addr, options: &Options) -> Vec<JSToken> {
+    let mut tokens = vec![JSToken::And, JSToken::Not, JSToken::ArrayIsArray(addr.clone())];
+    if options.plain_objects {
+        tokens.extend([JSToken::And, JSToken::PlainObject(addr)]);
+    }
+    tokens
+}
+
+fn typeof_token(addr: Addr, js_type: JSType) -> Vec<JSToken> {
+    vec![
+        JSToken::Typeof,
+        JSToken::Addr(addr),
+        JSToken::EqEqEq,
+        JSToken::JSType(js_type),
+    ]
+}
+
+fn strict_eq(left: JSToken, right: JSToken) -> Vec<JSToken> {
+    vec![left, JSToken::EqEqEq, right]
+}
+
+fn loose_not_eq(left: JSToken, right: JSToken) -> Vec<JSToken> {
+    vec![left, JSToken::NotEq, right]
+}
+
+pub fn js_tokens_to_string(tokens: Vec<JSToken>) -> String {
+    tokens
+        .par_iter()
+        .map(|i| {
+            match i {
+                JSToken::String(s) => {
+                    format!("\"{}\"", escape_double_q(s.clone()))
+                }
+                JSToken::Addr(addr) => addr_to_string(addr.clone()),
+                JSToken::Id(id) => id.clone(),
+                JSToken::Number(n) => n.clone(),
+                JSToken::EqEq => "==".to_string(),
+                JSToken::EqEqEq => "===".to_string(),
+                JSToken::NotEq => "!=".to_string(),
+                JSToken::NotEqEq => "!==".to_string(),
+                JSToken::In => "in ".to_string(),
+                JSToken::And => "&&".to_string(),
+                JSToken::Or => "||".to_string(),
+                JSToken::LPar => "(".to_string(),
+                JSToken::RPar => ")".to_string(),
+                JSToken::Typeof => "typeof ".to_string(),
+                JSToken::Not => "!".to_string(),
+                JSToken::True => "true".to_string(),
+                JSToken::False => "false".to_string(),
+                JSToken::Undefined => "undefined".to_string(),
+                JSToken::Null => "null".to_string(),
+                JSToken::None => "".to_string(),
+                JSToken::ArrayIsArray(addr) => format!("Array.isArray({})", addr_to_string(addr.clone())),
+                JSToken::PlainObject(addr) => {
+                    let a = addr_to_string(addr.clone());
+                    format!("(Object.getPrototypeOf({a})===Object.prototype||Object.getPrototypeOf({a})===null)")
+                }
+                JSToken::AddrLength(addr) => format!("{}.length", addr_to_string(addr.clone())),
+                JSToken::ObjectKeysLength(addr) => format!("Object.keys({}).length", addr_to_string(addr.clone())),
+                JSToken::HasOwn(addr, key) => format!(
+                    "Object.prototype.hasOwnProperty.call({},\"{}\")",
+                    addr_to_string(addr.clone()),
+                    escape_double_q(key.clone())
+                ),
+                JSToken::Call(f, addr) => format!("{f}({})", addr_to_string(addr.clone())),
+                JSToken::Apply(f, addr, args) => format!("{f}({},{})", addr_to_string(addr.clone()), args.join(",")),
+                JSToken::Span(span) => span.0.map_or(String::new(), source_map::marker),
+                JSToken::Refine(addr, c) => constraint_to_string(addr_to_string(addr.clone()), c),
+                // Guards are single expressions, so the `switch` goes in an arrow function that is called right away.
+                JSToken::Switch(addr, cases) => {
+                    let cases = cases
+                        .iter()
+                        .map(|(labels, body)| {
+                            let labels = labels
+                                .iter()
+                                .map(|l| format!("case {}:", js_tokens_to_string(vec![l.clone()])))
+                                .collect::<String>();
+                            format!("{labels}return({});", js_tokens_to_string(body.clone()))
+                        })
+                        .collect::<String>();
+                    format!(
+                        "(()=>{{switch({}){{{cases}default:return false}}}})()",
+                        addr_to_string(addr.clone())
+                    )
+                }
+                JSToken::JSType(t) => String::from(match t {
+                    JSType::String => "\"string\"",
+                    JSType::Number => "\"number\"",
+                    JSType::Function => "\"function\"",
+                    JSType::Boolean => "\"boolean\"",
+                    JSType::Object => "\"object\"",
+                    JSType::Undefined => "\"undefined\"",
+                    JSType::Symbol => "\"symbol\"",
+                    JSType::BigInt => "\"bigint\"",
+                }),
+                // _ => "".to_string(),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join("")
+}
+
+pub fn constraint_to_string(a: String, constraint: &Constraint) -> String {
+    match constraint {
+        Constraint::Minimum(n) => format!("(typeof {a}!==\"number\"||{a}>={n})"),
+        Constraint::Maximum(n) => format!("(typeof {a}!==\"number\"||{a}<={n})"),
+        Constraint::ExclusiveMinimum(n) => format!("(typeof {a}!==\"number\"||{a}>{n})"),
+        Constraint::ExclusiveMaximum(n) => format!("(typeof {a}!==\"number\"||{a}<{n})"),
+        Constraint::Integer => format!("(typeof {a}!==\"number\"||Number.isInteger({a}))"),
+        Constraint::MinLength(n) => format!("(typeof {a}!==\"string\"||{a}.length>={n})"),
+        Constraint::MaxLength(n) => format!("(typeof {a}!==\"string\"||{a}.length<={n})"),
+        Constraint::Pattern(re) => format!("(typeof {a}!==\"string\"||{re}.test({a}))"),
+        Constraint::MinItems(n) => format!("(!Array.isArray({a})||{a}.length>={n})"),
+        Constraint::MaxItems(n) => format!("(!Array.isArray({a})||{a}.length<={n})"),
+        Constraint::UniqueItems => format!("(!Array.isArray({a})||new Set({a}).size==={a}.length)"),
+        Constraint::Format(Format::DateTime) => format!(
+            "(typeof {a}!==\"string\"||{}.test({a})&&!isNaN(Date.parse({a})))",
+            Format::DateTime.regex()
+        ),
+        Constraint::Format(f) => format!("(typeof {a}!==\"string\"||{}.test({a}))", f.regex()),
+    }
+}
+
+/// Keys are always escaped string subscripts, so `__proto__` or `constructor` can't break out of the expression, and
+/// since every key is checked with `has_key` first the read resolves to the object's own value.
+pub fn addr_to_string(addr: Addr) -> String {
+    let mut temp = vec![addr[0].clone()];
+    for i in addr.iter().skip(1) {
+        temp.push(format!("[\"{}\"]", escape_double_q(i.clone())));
+    }
+    temp.join("")
+}
+
+pub fn function_dec(
+    name: String,
+    params: &[String],
+    return_body: String,
+    extension: Extension,
+    naming: &Naming,
+) -> String {
+    let guards = guard_params(params, extension, naming);
+    let guard = naming.guard(&name);
+    match extension {
+        Extension::JS => format!("export function {guard}(o{guards}){{return({return_body})}}"),
+        Extension::TS => {
+            let t = type_args(params);
+            format!("export function {guard}{t}(o: unknown{guards}): o is {name}{t}{{return({return_body})}}")
+        }
+        Extension::DTS => {
+            let t = type_args(params);
+            format!("export declare function {guard}{t}(o: unknown{guards}): o is {name}{t};")
+        }
+    }
+}
+
+/// `<T,U>` for a generic interface, empty otherwise.
+pub fn type_args(params: &[String]) -> String {
+    if params.is_empty() {
+        String::new()
+    } else {
+        format!("<{}>", params.join(","))
+    }
+}
+
+/// Generic interfaces take a guard for every type parameter, e.g. `isPage(o, isT)`.
+pub fn guard_params(params: &[String], extension: Extension, naming: &Naming) -> String {
+    params
+        .iter()
+        .map(|p| match extension {
+            Extension::JS => format!(",{}", naming.guard(p)),
+            Extension::TS | Extension::DTS => format!(",{}: (x: unknown) => x is {p}", naming.guard(p)),
+        })
+        .collect()
+}
+
+/// Calls the guard of the generic interface `name` used in its own declaration, passing a guard for each of `args`:
+/// the guard of a type parameter, interface or `--guards` type as is, otherwise an arrow function.
+pub fn generic_call(name: &str, args: &[Value], addr: Addr, options: &Options) -> JSToken {
+    let args = args
+        .iter()
+        .map(|arg| match unparen(arg) {
+            Value::Type(Type::Param(p)) => options.naming.guard(p),
+            Value::Entry(Entry {
+                key: Key::Ref(n),
+                value,
+                ..
+            }) if value.is_empty() => options.naming.guard(n),
+            Value::Type(Type::Custom(n)) if options.guards.iter().any(|(i, _)| i == n) => {
+                options.guards.iter().find(|(i, _)| i == n).unwrap().1.name.clone()
+            }
+            arg => {
+                let body = js_tokens_to_string(optimize(to_js_token(arg.clone(), vec!["x".to_string()], options)));
+                match options.extension {
+                    Extension::JS => format!("(x)=>{body}"),
+                    Extension::TS | Extension::DTS => {
+                        format!("(x: unknown): x is {}=>{body}", value_to_ts_string(arg))
+                    }
+                }
+            }
+        })
+        .collect();
+    JSToken::Apply(options.naming.guard(name), addr, args)
+}
+
+pub fn guard_args(params: &[String], naming: &Naming) -> String {
+    params.iter().map(|p| format!(",{}", naming.guard(p))).collect()
+}
+
+pub fn return_body(entries_len: usize, return_body: Vec<JSToken>, options: &Options) -> Vec<JSToken> {
+    let addr = vec!["o".to_string()];
+    [
+        loose_not_eq(JSToken::Addr(addr.clone()), JSToken::Null),
+        vec![JSToken::And],
+        typeof_token(addr.clone(), JSType::Object),
+        object_shape(addr.clone(), options),
+        vec![JSToken::And],
+        strict_eq(
+            JSToken::ObjectKeysLength(addr),
+            JSToken::Number(entries_len.to_string()),
+        ),
+        return_body,
+    ]
+    .concat()
+}
+
+pub fn escape_double_q(string: String) -> String {
+    let mut string = Vec::from(string);
+    let mut i = 0;
+    while i < string.len() {
+        let c = string[i] as char;
+        if c == '"' {
+            if i == 0 {
+                string.insert(i, b'\\');
+                i += 1;
+            } else {
+                if string[i - 1] != b'\\' {
+                    string.insert(i, b'\\');
+                    i += 1;
+                }
+            }
+        }
+        i += 1;
+    }
+    String::from_utf8(string).unwrap()
+}
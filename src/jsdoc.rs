@@ -0,0 +1,210 @@
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Doc {
+    pub description: String,
+    pub tags: Vec<Tag>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tag {
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Constraint {
+    Minimum(String),
+    Maximum(String),
+    ExclusiveMinimum(String),
+    ExclusiveMaximum(String),
+    Integer,
+    MinLength(String),
+    MaxLength(String),
+    Pattern(String),
+    MinItems(String),
+    MaxItems(String),
+    UniqueItems,
+    Format(Format),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Format {
+    Email,
+    Uuid,
+    DateTime,
+    Uri,
+}
+
+/// Parses the text between `/**` and `*/`.
+pub fn parse_doc(raw: &str) -> Doc {
+    let mut doc = Doc::default();
+    let mut description: Vec<&str> = Vec::new();
+    for line in raw.lines() {
+        let line = line.trim();
+        let line = line.strip_prefix('*').unwrap_or(line).trim();
+        // A tag runs until the next line starting with one, so values such as a `@pattern` may contain ` @`.
+        if let Some(tag) = line.strip_prefix('@') {
+            let (name, value) = tag.split_once(char::is_whitespace).unwrap_or((tag, ""));
+            doc.tags.push(Tag {
+                name: name.to_string(),
+                value: value.trim().to_string(),
+            });
+        } else if let Some(tag) = doc.tags.last_mut() {
+            if !line.is_empty() {
+                tag.value = format!("{} {}", tag.value, line).trim().to_string();
+            }
+        } else {
+            description.push(line);
+        }
+    }
+    doc.description = description.join("\n").trim().to_string();
+    doc
+}
+
+impl Doc {
+    pub fn constraints(&self) -> Vec<Constraint> {
+        self.tags
+            .iter()
+            .filter_map(|tag| {
+                let number = || {
+                    if !is_number_literal(&tag.value) {
+                        panic!("@{} expects a number, found \"{}\"", tag.name, tag.value);
+                    }
+                    tag.value.clone()
+                };
+                Some(match tag.name.as_str() {
+                    "minimum" => Constraint::Minimum(number()),
+                    "maximum" => Constraint::Maximum(number()),
+                    "exclusiveMinimum" => Constraint::ExclusiveMinimum(number()),
+                    "exclusiveMaximum" => Constraint::ExclusiveMaximum(number()),
+                    "integer" => Constraint::Integer,
+                    "minLength" => Constraint::MinLength(number()),
+                    "maxLength" => Constraint::MaxLength(number()),
+                    "pattern" => Constraint::Pattern(regex_literal(&tag.value)),
+                    "minItems" => Constraint::MinItems(number()),
+                    "maxItems" => Constraint::MaxItems(number()),
+                    "uniqueItems" => Constraint::UniqueItems,
+                    // Other formats are left to other tools reading the same docs.
+                    "format" => Constraint::Format(match tag.value.as_str() {
+                        "email" => Format::Email,
+                        "uuid" => Format::Uuid,
+                        "date-time" => Format::DateTime,
+                        "uri" => Format::Uri,
+                        _ => return None,
+                    }),
+                    _ => return None,
+                })
+            })
+            .collect()
+    }
+
+    /// The value of the first `@example` tag.
+    pub fn example(&self) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|tag| tag.name == "example")
+            .map(|tag| tag.value.trim())
+    }
+}
+
+/// A finite decimal number as JavaScript writes it, optionally negative: `-1`, `0.5`, `.5`, `2e3`. Rust also parses
+/// `inf` and `NaN`, which would end up as undefined identifiers in the generated code.
+pub fn is_number_literal(value: &str) -> bool {
+    let unsigned = value.strip_prefix('-').unwrap_or(value);
+    let (mantissa, exponent) = match unsigned.split_once(['e', 'E']) {
+        Some((m, e)) => (m, Some(e.strip_prefix(['+', '-']).unwrap_or(e))),
+        None => (unsigned, None),
+    };
+    let digits = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    let mantissa = match mantissa.split_once('.') {
+        Some((int, frac)) => (int.is_empty() || digits(int)) && (frac.is_empty() || digits(frac)) && mantissa != ".",
+        None => digits(mantissa),
+    };
+    mantissa && exponent.is_none_or(digits) && value.parse::<f64>().is_ok_and(f64::is_finite)
+}
+
+/// `@pattern` accepts either a regex literal (`/^a+$/i`) or a bare pattern (`^a+$`). Anything that isn't exactly one
+/// regex literal is taken as a bare pattern, so the result is always a single regex literal token.
+fn regex_literal(pattern: &str) -> String {
+    if let Some(end) = pattern.strip_prefix('/').and_then(regex_body_end) {
+        let flags = &pattern[end + 2..];
+        let unique = flags.chars().enumerate().all(|(i, c)| !flags[..i].contains(c));
+        if end > 0 && unique && flags.chars().all(|c| "dgimsuvy".contains(c)) {
+            return pattern.to_string();
+        }
+    }
+    let mut literal = String::from("/");
+    let mut escaped = false;
+    for c in pattern.chars() {
+        if c == '/' && !escaped {
+            literal.push('\\');
+        }
+        escaped = c == '\\' && !escaped;
+        literal.push(c);
+    }
+    if escaped {
+        // A trailing backslash would escape the closing `/`.
+        literal.push('\\');
+    }
+    if literal == "/" {
+        // `//` starts a comment.
+        literal.push_str("(?:)");
+    }
+    literal.push('/');
+    literal
+}
+
+/// The byte index of the `/` ending the body of a regex literal, skipping escapes and `/` in character classes.
+fn regex_body_end(body: &str) -> Option<usize> {
+    let mut escaped = false;
+    let mut class = false;
+    for (i, c) in body.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '[' => class = true,
+            ']' => class = false,
+            '/' if !class => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+impl Constraint {
+    pub fn describe(&self) -> String {
+        match self {
+            Constraint::Minimum(n) => format!(">= {n}"),
+            Constraint::Maximum(n) => format!("<= {n}"),
+            Constraint::ExclusiveMinimum(n) => format!("> {n}"),
+            Constraint::ExclusiveMaximum(n) => format!("< {n}"),
+            Constraint::Integer => "integer".to_string(),
+            Constraint::MinLength(n) => format!("length >= {n}"),
+            Constraint::MaxLength(n) => format!("length <= {n}"),
+            Constraint::Pattern(p) => format!("match {p}"),
+            Constraint::MinItems(n) => format!("at least {n} items"),
+            Constraint::MaxItems(n) => format!("at most {n} items"),
+            Constraint::UniqueItems => "unique items".to_string(),
+            Constraint::Format(f) => format!("{} format", f.name()),
+        }
+    }
+}
+
+impl Format {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Format::Email => "email",
+            Format::Uuid => "uuid",
+            Format::DateTime => "date-time",
+            Format::Uri => "uri",
+        }
+    }
+
+    pub fn regex(&self) -> &'static str {
+        match self {
+            Format::Email => r"/^[^\s@]+@[^\s@]+\.[^\s@]+$/",
+            Format::Uuid => r"/^[0-9a-f]{8}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{12}$/i",
+            Format::DateTime => r"/^\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}(\.\d+)?(Z|[+-]\d{2}:\d{2})$/i",
+            Format::Uri => r"/^[a-z][a-z\d+.-]*:[^\s]*$/i",
+        }
+    }
+}
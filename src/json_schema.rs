@@ -0,0 +1,363 @@
+use crate::jsdoc::{Constraint, Doc, Format};
+use crate::lexer::Type;
+use crate::parsers::{brand, or_branches, Entry, Generic, Key, Value};
+
+/// Just enough of JSON to write schemas.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(String),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    fn string(string: &str) -> Json {
+        Json::String(string.to_string())
+    }
+
+    fn object(props: Vec<(&str, Json)>) -> Json {
+        Json::Object(props.into_iter().map(|(k, v)| (k.to_string(), v)).collect())
+    }
+
+    fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(props) => props.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    /// Adds `key` to an object, replacing any previous value.
+    fn set(&mut self, key: &str, value: Json) {
+        if let Json::Object(props) = self {
+            match props.iter_mut().find(|(k, _)| k == key) {
+                Some((_, v)) => *v = value,
+                None => props.push((key.to_string(), value)),
+            }
+        }
+    }
+
+    /// Renders like `JSON.stringify(json, null, 2)`.
+    pub fn to_pretty_string(&self, depth: usize) -> String {
+        let indent = "  ".repeat(depth + 1);
+        let end = "  ".repeat(depth);
+        match self {
+            Json::Null => "null".to_string(),
+            Json::Bool(b) => b.to_string(),
+            Json::Number(n) => n.clone(),
+            Json::String(s) => json_string(s),
+            Json::Array(items) if items.is_empty() => "[]".to_string(),
+            Json::Array(items) => format!(
+                "[\n{}\n{end}]",
+                items
+                    .iter()
+                    .map(|i| format!("{indent}{}", i.to_pretty_string(depth + 1)))
+                    .collect::<Vec<String>>()
+                    .join(",\n")
+            ),
+            Json::Object(props) if props.is_empty() => "{}".to_string(),
+            Json::Object(props) => format!(
+                "{{\n{}\n{end}}}",
+                props
+                    .iter()
+                    .map(|(k, v)| format!("{indent}{}: {}", json_string(k), v.to_pretty_string(depth + 1)))
+                    .collect::<Vec<String>>()
+                    .join(",\n")
+            ),
+        }
+    }
+
+    /// Renders as block-style YAML, indented by `indent` spaces. Scalars and empty containers are rendered inline.
+    pub fn to_yaml_string(&self, indent: usize) -> String {
+        let pad = " ".repeat(indent);
+        let block = |json: &Json| {
+            matches!(json, Json::Array(i) if !i.is_empty()) || matches!(json, Json::Object(i) if !i.is_empty())
+        };
+        match self {
+            Json::String(s) => yaml_scalar(s),
+            Json::Array(items) if !items.is_empty() => items
+                .iter()
+                .map(|i| match block(i) {
+                    true => format!("{pad}- {}", &i.to_yaml_string(indent + 2)[indent + 2..]),
+                    false => format!("{pad}- {}", i.to_yaml_string(0)),
+                })
+                .collect::<Vec<String>>()
+                .join("\n"),
+            Json::Object(props) if !props.is_empty() => props
+                .iter()
+                .map(|(k, v)| match block(v) {
+                    true => format!("{pad}{}:\n{}", yaml_scalar(k), v.to_yaml_string(indent + 2)),
+                    false => format!("{pad}{}: {}", yaml_scalar(k), v.to_yaml_string(0)),
+                })
+                .collect::<Vec<String>>()
+                .join("\n"),
+            json => json.to_pretty_string(0),
+        }
+    }
+}
+
+/// Plain if it can't be mistaken for another type or for YAML syntax, double-quoted with JSON escapes otherwise.
+fn yaml_scalar(string: &str) -> String {
+    let plain = string.starts_with(|c: char| c.is_ascii_alphabetic() || c == '$')
+        && string.chars().all(|c| c.is_ascii_alphanumeric() || "$_-".contains(c))
+        && !["true", "false", "null", "yes", "no", "on", "off", "y", "n"].contains(&string.to_lowercase().as_str());
+    if plain {
+        string.to_string()
+    } else {
+        json_string(string)
+    }
+}
+
+pub fn json_string(string: &str) -> String {
+    let mut escaped = String::from("\"");
+    for c in string.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+/// A draft 2020-12 schema with one `$defs` entry per interface.
+pub fn interfaces_to_json_schema(interfaces: &[Entry]) -> String {
+    Json::object(vec![
+        ("$schema", Json::string("https://json-schema.org/draft/2020-12/schema")),
+        ("$defs", Json::Object(definitions(interfaces, "#/$defs/"))),
+    ])
+    .to_pretty_string(0)
+        + "\n"
+}
+
+/// An OpenAPI 3.1 document holding only `components.schemas`, as YAML or JSON.
+pub fn interfaces_to_openapi(interfaces: &[Entry], yaml: bool) -> String {
+    let document = Json::object(vec![(
+        "components",
+        Json::object(vec![(
+            "schemas",
+            Json::Object(definitions(interfaces, "#/components/schemas/")),
+        )]),
+    )]);
+    if yaml {
+        document.to_yaml_string(0) + "\n"
+    } else {
+        document.to_pretty_string(0) + "\n"
+    }
+}
+
+/// Schemas of the interfaces by name, referring to each other by `ref_prefix` followed by the name.
+pub fn definitions(interfaces: &[Entry], ref_prefix: &str) -> Vec<(String, Json)> {
+    let names: Vec<&String> = interfaces
+        .iter()
+        .map(|i| match &i.key {
+            Key::Name(name) => name,
+            _ => panic!("Name of interface not found"),
+        })
+        .collect();
+    let schema = Schema {
+        names: &names,
+        ref_prefix,
+    };
+    interfaces
+        .iter()
+        .zip(&names)
+        .map(|(i, name)| {
+            let mut json = schema.object(&i.value);
+            describe(&mut json, &i.doc);
+            (name.to_string(), json)
+        })
+        .collect()
+}
+
+struct Schema<'a> {
+    names: &'a [&'a String],
+    ref_prefix: &'a str,
+}
+
+impl Schema<'_> {
+    fn value(&self, value: &Value) -> Json {
+        match value {
+            Value::Type(t) => leaf(t),
+            Value::Entry(e) => match &e.key {
+                Key::None => self.object(&e.value),
+                Key::Ref(n) | Key::Generic(Generic::Ref(n)) if self.names.contains(&n) => {
+                    Json::object(vec![("$ref", Json::String(format!("{}{n}", self.ref_prefix)))])
+                }
+                Key::Name(_) | Key::Optional(_) | Key::Paren | Key::Alias(_) | Key::Ref(_) => self.values(&e.value),
+                Key::Generic(Generic::Array) => {
+                    Json::object(vec![("type", Json::string("array")), ("items", self.values(&e.value))])
+                }
+                Key::Generic(Generic::Custom(_) | Generic::Ref(_)) => Json::object(vec![]),
+                Key::Tuple => Json::object(vec![
+                    ("type", Json::string("array")),
+                    (
+                        "prefixItems",
+                        Json::Array(e.value.iter().map(|i| self.value(i)).collect()),
+                    ),
+                    ("items", Json::Bool(false)),
+                    ("minItems", Json::Number(e.value.len().to_string())),
+                ]),
+                Key::Or => {
+                    // JSON has no `undefined`, so `a?: T | undefined` is just an optional `T`.
+                    let branches: Vec<Value> = or_branches(e)
+                        .into_iter()
+                        .filter(|i| *i != Value::Type(Type::Undefined))
+                        .collect();
+                    let literals: Option<Vec<Json>> = branches
+                        .iter()
+                        .map(|i| match i {
+                            Value::Type(t) => literal(t),
+                            _ => None,
+                        })
+                        .collect();
+                    match (literals, branches.len()) {
+                        (_, 1) => self.value(&branches[0]),
+                        (Some(literals), _) => Json::object(vec![("enum", Json::Array(literals))]),
+                        (None, _) => Json::object(vec![(
+                            "anyOf",
+                            Json::Array(branches.iter().map(|i| self.value(i)).collect()),
+                        )]),
+                    }
+                }
+                Key::And => match brand(e) {
+                    Some(b) => self.value(&b.base),
+                    None => Json::object(vec![(
+                        "allOf",
+                        Json::Array(e.value.iter().map(|i| self.value(i)).collect()),
+                    )]),
+                },
+            },
+        }
+    }
+
+    /// Schemas that must all pass, combined with `allOf` if there is more than one.
+    fn values(&self, values: &[Value]) -> Json {
+        let mut schemas: Vec<Json> = values
+            .iter()
+            .filter(|i| !matches!(i, Value::Type(Type::Oper(_) | Type::Punct(_))))
+            .map(|i| self.value(i))
+            .collect();
+        match schemas.len() {
+            0 => Json::object(vec![]),
+            1 => schemas.remove(0),
+            _ => Json::object(vec![("allOf", Json::Array(schemas))]),
+        }
+    }
+
+    /// Guards reject keys that aren't declared, hence `additionalProperties: false`.
+    fn object(&self, props: &[Value]) -> Json {
+        let mut properties = Vec::new();
+        let mut required = Vec::new();
+        for i in props {
+            if let Value::Entry(
+                p @ Entry {
+                    key: Key::Name(n) | Key::Optional(n),
+                    ..
+                },
+            ) = i
+            {
+                if let Key::Name(_) = p.key {
+                    required.push(Json::string(n));
+                }
+                let mut json = self.values(&p.value);
+                describe(&mut json, &p.doc);
+                properties.push((n.clone(), json));
+            }
+        }
+        Json::object(vec![
+            ("type", Json::string("object")),
+            ("properties", Json::Object(properties)),
+            ("required", Json::Array(required)),
+            ("additionalProperties", Json::Bool(false)),
+        ])
+    }
+}
+
+fn leaf(t: &Type) -> Json {
+    let of_type = |name: &str| Json::object(vec![("type", Json::string(name))]);
+    match t {
+        Type::String => of_type("string"),
+        Type::Number => of_type("number"),
+        Type::Boolean => of_type("boolean"),
+        Type::Null => of_type("null"),
+        Type::Object => of_type("object"),
+        // JSON has no bigint, `--revive` reads them from integers or integer strings.
+        Type::BigInt => Json::object(vec![
+            (
+                "type",
+                Json::Array(vec![Json::string("integer"), Json::string("string")]),
+            ),
+            ("pattern", Json::string("^-?[0-9]+$")),
+        ]),
+        Type::Custom(name) if name == "Date" => Json::object(vec![
+            ("type", Json::string("string")),
+            ("format", Json::string("date-time")),
+        ]),
+        Type::Undefined | Type::Symbol | Type::Function => Json::object(vec![("not", Json::object(vec![]))]),
+        t => match literal(t) {
+            Some(l) => Json::object(vec![("const", l)]),
+            None => Json::object(vec![]),
+        },
+    }
+}
+
+fn literal(t: &Type) -> Option<Json> {
+    match t {
+        Type::StringLit(s) => Some(Json::string(s)),
+        Type::NumberLit(n) => Some(Json::Number(n.replace('_', ""))),
+        Type::True => Some(Json::Bool(true)),
+        Type::False => Some(Json::Bool(false)),
+        Type::Null => Some(Json::Null),
+        _ => None,
+    }
+}
+
+/// Adds the description and constraint tags of a JSDoc comment as schema keywords.
+fn describe(json: &mut Json, doc: &Option<Doc>) {
+    let Some(doc) = doc else { return };
+    if !doc.description.is_empty() {
+        json.set("description", Json::string(&doc.description));
+    }
+    for c in doc.constraints() {
+        let (key, value) = match c {
+            Constraint::Minimum(n) => ("minimum", Json::Number(n)),
+            Constraint::Maximum(n) => ("maximum", Json::Number(n)),
+            Constraint::ExclusiveMinimum(n) => ("exclusiveMinimum", Json::Number(n)),
+            Constraint::ExclusiveMaximum(n) => ("exclusiveMaximum", Json::Number(n)),
+            Constraint::Integer if json.get("type") == Some(&Json::string("number")) => {
+                ("type", Json::string("integer"))
+            }
+            Constraint::Integer => ("multipleOf", Json::Number("1".to_string())),
+            Constraint::MinLength(n) => ("minLength", Json::Number(n)),
+            Constraint::MaxLength(n) => ("maxLength", Json::Number(n)),
+            Constraint::Pattern(p) => ("pattern", Json::String(pattern(&p))),
+            Constraint::MinItems(n) => ("minItems", Json::Number(n)),
+            Constraint::MaxItems(n) => ("maxItems", Json::Number(n)),
+            Constraint::UniqueItems => ("uniqueItems", Json::Bool(true)),
+            Constraint::Format(f) => (
+                "format",
+                Json::string(match f {
+                    Format::Email => "email",
+                    Format::Uuid => "uuid",
+                    Format::DateTime => "date-time",
+                    Format::Uri => "uri",
+                }),
+            ),
+        };
+        json.set(key, value);
+    }
+}
+
+/// The source of a regex literal, without its flags, which JSON Schema patterns don't have.
+fn pattern(literal: &str) -> String {
+    let end = literal.rfind('/').unwrap_or(literal.len());
+    literal[1..end].replace("\\/", "/")
+}
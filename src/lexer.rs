@@ -1,300 +1,469 @@
-use crate::next;
-use std::str;
-
-#[derive(Debug, Clone, PartialEq)]
-pub enum Token {
-    Id(String),
-    Number(usize),
-    String(String),
-    Undefined(String),
-    Type(Type),
-    Key(String),
-    Interface,
-    Colon,
-    Eq,
-    Dash,
-    Slash,
-    Comment,
-    QM,  // QuestionMark
-    EOE, // EndOfEntry
-    EOF, // EndOfFile
-}
-
-#[derive(Debug, Clone, PartialEq)]
-pub enum Type {
-    Custom(String),
-    Oper(Oper),
-    StringLit(String),
-    NumberLit(String),
-    Punct(Punct),
-    True,
-    False,
-    String,
-    Number,
-    Object,
-    Boolean,
-    Null,
-    Undefined,
-    Function,
-    Symbol,
-    BigInt,
-    Any,
-    Unknown,
-}
-
-#[derive(Debug, Clone, PartialEq)]
-pub enum Oper {
-    And,
-    Or,
-}
-
-#[derive(Debug, Clone, PartialEq)]
-pub enum Punct {
-    LBrace,
-    RBrace,
-    LBrack,
-    RBrack,
-    LPar,
-    RPar,
-    LAngleB,
-    RAngleB,
-}
-
-pub fn is_skippable(char: &char) -> bool {
-    char == &'\0' || char == &' ' || char == &'\t' || char == &'\r' || char == &'\n'
-}
-
-pub fn find_interfaces(string: String) -> String {
-    let mut result_indexes: Vec<(usize, usize)> = Vec::new();
-    let mut result_string = String::new();
-    let string = Vec::from(string);
-    let string_len = string.len();
-    let mut i = 0;
-    while i < string_len - 1 {
-        let c = string[i] as char;
-        match c {
-            '"' => {
-                let (_, end) = string_lit_double_q(&string, i);
-                i = end;
-            }
-            '\'' => {
-                let (_, end) = string_lit_single_q(&string, i);
-                i = end;
-            }
-            '`' => {
-                let (_, end) = string_lit_backtick(&string, i);
-                i = end;
-            }
-            '/' => {
-                if string[i + 1] as char == '/' {
-                    let mut j = i + 1;
-                    while j < string_len && string[j] as char != '\n' {
-                        j += 1;
-                    }
-                    i = j;
-                }
-            }
-            _ => {
-                if c.is_alphabetic() {
-                    let start = i;
-                    let mut j = i + 1;
-                    while j < string_len && (string[j] as char).is_alphabetic() && !is_skippable(&(string[j] as char)) {
-                        j += 1;
-                    }
-                    let x = str::from_utf8(&string[start..j]).unwrap();
-                    if x == "interface" {
-                        let mut k = j + 1;
-                        let mut brace_count = 0;
-                        let mut first_brace_seen = false;
-                        while k < string_len {
-                            let c = string[k] as char;
-                            match c {
-                                '"' => {
-                                    let (_, end) = string_lit_double_q(&string, k);
-                                    k = end;
-                                }
-                                '\'' => {
-                                    let (_, end) = string_lit_single_q(&string, k);
-                                    k = end;
-                                }
-                                '`' => {
-                                    let (_, end) = string_lit_backtick(&string, k);
-                                    k = end;
-                                }
-                                '/' => {
-                                    if string[j + 1] as char == '/' {
-                                        let mut k = j + 1;
-                                        while k < string_len && string[k] as char != '\n' {
-                                            k += 1;
-                                        }
-                                        j = k;
-                                    }
-                                }
-                                _ => {
-                                    if c == '{' {
-                                        if !first_brace_seen {
-                                            first_brace_seen = true
-                                        };
-                                        brace_count += 1;
-                                    } else if c == '}' {
-                                        brace_count -= 1;
-                                        if brace_count < 0 {
-                                            break;
-                                        }
-                                    }
-                                }
-                            }
-                            if first_brace_seen && brace_count == 0 {
-                                result_indexes.push((start, k));
-                                i = k;
-                                break;
-                            }
-                            k += 1;
-                        }
-                    }
-                }
-            }
-        }
-        i += 1;
-    }
-
-    for i in result_indexes {
-        result_string.push_str(str::from_utf8(&string[i.0..=i.1]).unwrap());
-    }
-
-    result_string
-}
-
-pub fn tokenize(src: String) -> Vec<Token> {
-    let mut tokens: Vec<Token> = Vec::new();
-    let src_vec = Vec::from(src);
-    let src_vec_len = src_vec.len();
-    let mut i: usize = 0;
-
-    while i < src_vec_len {
-        let c: char = src_vec[i] as char;
-
-        if is_skippable(&c) {
-            i += 1;
-            continue;
-        }
-
-        tokens.push(match c {
-            '/' => {
-                next! { src_vec, i,
-                    Token::Slash,
-                    '/' => {
-                        let mut j = i + 1;
-                        while j < src_vec_len && src_vec[j] as char != '\n' {
-                            j += 1;
-                        }
-                        i = j;
-                        Token::Comment
-                    },
-                }
-            }
-            '"' => {
-                let (start, end) = string_lit_double_q(&src_vec, i);
-                let string = String::from_utf8_lossy(&src_vec[(start + 1)..end]).to_string();
-                i = end;
-                Token::String(string)
-            }
-            '\'' => {
-                let (start, end) = string_lit_single_q(&src_vec, i);
-                let string = String::from_utf8_lossy(&src_vec[(start + 1)..end]).to_string();
-                i = end;
-                Token::String(string)
-            }
-            '&' => Token::Type(Type::Oper(Oper::And)),
-            '|' => Token::Type(Type::Oper(Oper::Or)),
-            '-' => Token::Dash,
-            '<' => Token::Type(Type::Punct(Punct::LAngleB)),
-            '>' => Token::Type(Type::Punct(Punct::RAngleB)),
-            '(' => Token::Type(Type::Punct(Punct::LPar)),
-            ')' => Token::Type(Type::Punct(Punct::RPar)),
-            '[' => Token::Type(Type::Punct(Punct::LBrack)),
-            ']' => Token::Type(Type::Punct(Punct::RBrack)),
-            '{' => Token::Type(Type::Punct(Punct::LBrace)),
-            '}' => Token::Type(Type::Punct(Punct::RBrace)),
-            '=' => Token::Eq,
-            ':' => Token::Colon,
-            '?' => Token::QM,
-            _ => {
-                if c.is_alphabetic() || c == '_' || c == '$' {
-                    let mut _c = c;
-                    let mut temp: String = String::new();
-                    let mut j: usize = i;
-                    while (!is_skippable(&_c) && _c.is_alphanumeric()) || _c == '_' || _c == '$' {
-                        temp += _c.to_string().as_str();
-                        j += 1;
-                        if j == src_vec_len {
-                            break;
-                        }
-                        _c = src_vec[j] as char;
-                    }
-                    i = j - 1;
-                    match temp.as_str() {
-                        "interface" => Token::Interface,
-                        "true" => Token::Type(Type::True),
-                        "false" => Token::Type(Type::False),
-                        "string" => Token::Type(Type::String),
-                        "number" => Token::Type(Type::Number),
-                        "object" => Token::Type(Type::Object),
-                        "boolean" => Token::Type(Type::Boolean),
-                        "null" => Token::Type(Type::Null),
-                        "undefined" => Token::Type(Type::Undefined),
-                        "symbol" => Token::Type(Type::Symbol),
-                        "bigint" => Token::Type(Type::BigInt),
-                        "any" => Token::Type(Type::Any),
-                        "unknown" => Token::Type(Type::Unknown),
-                        "Function" => Token::Type(Type::Function),
-                        _ => Token::Id(temp),
-                    }
-                } else if c.is_numeric() {
-                    let mut _c = c;
-                    let mut temp: String = String::new();
-                    let mut j: usize = i;
-                    while (!is_skippable(&_c) && _c.is_numeric()) || _c == '_' {
-                        temp += _c.to_string().as_str();
-                        j += 1;
-                        if j == src_vec_len {
-                            break;
-                        }
-                        _c = src_vec[j] as char;
-                    }
-                    i = j - 1;
-                    Token::Number(temp.replace("_", "").parse().unwrap())
-                } else {
-                    Token::Undefined(c.to_string())
-                }
-            }
-        });
-        i += 1;
-    }
-    tokens.push(Token::EOF);
-    return tokens;
-}
-
-fn string_lit_double_q(string: &Vec<u8>, index_of_quote: usize) -> (usize, usize) {
-    let mut j = index_of_quote + 1;
-    while j < string.len() && (string[j] as char != '"' || string[j - 1] as char == '\\') {
-        j += 1;
-    }
-    (index_of_quote, j)
-}
-
-fn string_lit_single_q(string: &Vec<u8>, index_of_quote: usize) -> (usize, usize) {
-    let mut j = index_of_quote + 1;
-    while j < string.len() && (string[j] as char != '\'' || string[j - 1] as char == '\\') {
-        j += 1;
-    }
-    (index_of_quote, j)
-}
-
-fn string_lit_backtick(string: &Vec<u8>, index_of_backtick: usize) -> (usize, usize) {
-    let mut j = index_of_backtick + 1;
-    while j < string.len() && (string[j] as char != '`' || string[j - 1] as char == '\\') {
-        j += 1;
-    }
-    (index_of_backtick, j)
-}
+use crate::next;
+use std::str;
+
+#[derive(Debug, Clone, PartialEq)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum Token {
+    Id(String),
+    Number(usize),
+    String(String),
+    Undefined(String),
+    Type(Type),
+    Key(String),
+    Interface,
+    Colon,
+    Comma,
+    Eq,
+    Dash,
+    Slash,
+    Comment,
+    Doc(String),
+    QM,  // QuestionMark
+    EOE, // EndOfEntry
+    EOF, // EndOfFile
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Type {
+    Custom(String),
+    /// A type parameter of the generic interface being parsed.
+    Param(String),
+    Oper(Oper),
+    StringLit(String),
+    NumberLit(String),
+    Punct(Punct),
+    True,
+    False,
+    String,
+    Number,
+    Object,
+    Boolean,
+    Null,
+    Undefined,
+    Function,
+    Symbol,
+    BigInt,
+    Any,
+    Unknown,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Oper {
+    And,
+    Or,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Punct {
+    LBrace,
+    RBrace,
+    LBrack,
+    RBrack,
+    LPar,
+    RPar,
+    LAngleB,
+    RAngleB,
+    Comma,
+}
+
+pub fn is_skippable(char: &char) -> bool {
+    char == &'\0' || char == &' ' || char == &'\t' || char == &'\r' || char == &'\n'
+}
+
+/// Blanks out everything but the interface and type alias declarations and their JSDoc comments. Line breaks are kept
+/// and every other byte becomes a space, so offsets into the result are offsets into the source file.
+pub fn find_interfaces(string: String) -> String {
+    let mut result_indexes: Vec<(usize, usize)> = Vec::new();
+    let string = Vec::from(string);
+    let string_len = string.len();
+    let mut i = 0;
+    while i < string_len - 1 {
+        let c = string[i] as char;
+        match c {
+            '"' => {
+                let (_, end) = string_lit_double_q(&string, i);
+                i = end;
+            }
+            '\'' => {
+                let (_, end) = string_lit_single_q(&string, i);
+                i = end;
+            }
+            '`' => {
+                let (_, end) = string_lit_backtick(&string, i);
+                i = end;
+            }
+            '/' => {
+                if string[i + 1] as char == '/' {
+                    let mut j = i + 1;
+                    while j < string_len && string[j] as char != '\n' {
+                        j += 1;
+                    }
+                    i = j;
+                } else if string[i + 1] as char == '*' {
+                    let (_, end) = block_comment(&string, i);
+                    i = end;
+                }
+            }
+            _ => {
+                if c.is_alphabetic() {
+                    let start = i;
+                    let mut j = i + 1;
+                    while j < string_len && (string[j] as char).is_alphabetic() && !is_skippable(&(string[j] as char)) {
+                        j += 1;
+                    }
+                    let x = str::from_utf8(&string[start..j]).unwrap();
+                    if x == "interface" {
+                        let mut k = j + 1;
+                        let mut brace_count = 0;
+                        let mut first_brace_seen = false;
+                        while k < string_len {
+                            let c = string[k] as char;
+                            match c {
+                                '"' => {
+                                    let (_, end) = string_lit_double_q(&string, k);
+                                    k = end;
+                                }
+                                '\'' => {
+                                    let (_, end) = string_lit_single_q(&string, k);
+                                    k = end;
+                                }
+                                '`' => {
+                                    let (_, end) = string_lit_backtick(&string, k);
+                                    k = end;
+                                }
+                                '/' => {
+                                    if k + 1 < string_len && string[k + 1] as char == '/' {
+                                        while k < string_len && string[k] as char != '\n' {
+                                            k += 1;
+                                        }
+                                    } else if k + 1 < string_len && string[k + 1] as char == '*' {
+                                        let (_, end) = block_comment(&string, k);
+                                        k = end;
+                                    }
+                                }
+                                _ => {
+                                    if c == '{' {
+                                        if !first_brace_seen {
+                                            first_brace_seen = true
+                                        };
+                                        brace_count += 1;
+                                    } else if c == '}' {
+                                        brace_count -= 1;
+                                        if brace_count < 0 {
+                                            break;
+                                        }
+                                    }
+                                }
+                            }
+                            if first_brace_seen && brace_count == 0 {
+                                result_indexes.extend(doc_comment(&string, start));
+                                result_indexes.push((start, k));
+                                i = k;
+                                break;
+                            }
+                            k += 1;
+                        }
+                    } else if x == "type" {
+                        if let Some(end) = type_alias_end(&string, j) {
+                            result_indexes.extend(doc_comment(&string, start));
+                            result_indexes.push((start, end));
+                            i = end;
+                        }
+                    } else {
+                        i = j - 1;
+                    }
+                }
+            }
+        }
+        i += 1;
+    }
+
+    let mut result: Vec<u8> = string.iter().map(|c| if *c == b'\n' { b'\n' } else { b' ' }).collect();
+    for (start, end) in result_indexes {
+        result[start..=end].copy_from_slice(&string[start..=end]);
+    }
+    String::from_utf8(result).unwrap()
+}
+
+/// The first and last index of the `/** */` comment before the declaration at `start`, with nothing but `export` and
+/// `declare` in between.
+fn doc_comment(string: &[u8], start: usize) -> Option<(usize, usize)> {
+    let mut k = start;
+    loop {
+        while k > 0 && is_skippable(&(string[k - 1] as char)) {
+            k -= 1;
+        }
+        match ["export", "declare"]
+            .iter()
+            .find(|w| string[..k].ends_with(w.as_bytes()))
+        {
+            Some(word) => k -= word.len(),
+            None => break,
+        }
+    }
+    if !string[..k].ends_with(b"*/") || k < 4 {
+        return None;
+    }
+    let open = string[..k - 2].windows(2).rposition(|w| w == b"/*")?;
+    string[open..].starts_with(b"/**").then_some((open, k - 1))
+}
+
+/// Keywords only found in type expressions the parsers don't support: type operators, conditional and mapped types,
+/// and constructor types.
+const UNSUPPORTED_TYPE_WORDS: [&str; 6] = ["keyof", "typeof", "infer", "extends", "in", "new"];
+
+/// `index` is right after the `type` keyword. If it starts an alias like `type Id = string & { ... }`, returns the
+/// index of the `;` that ends it, or of the last character before the line break that does. Aliases of function,
+/// conditional or mapped types, or using `keyof` and the like, are left out.
+fn type_alias_end(string: &[u8], index: usize) -> Option<usize> {
+    let skip_whitespace = |mut k: usize| {
+        while k < string.len() && is_skippable(&(string[k] as char)) {
+            k += 1;
+        }
+        k
+    };
+    let mut k = skip_whitespace(index);
+    if k == index
+        || !string
+            .get(k)
+            .is_some_and(|c| (*c as char).is_alphabetic() || *c == b'_' || *c == b'$')
+    {
+        return None;
+    }
+    while k < string.len() && ((string[k] as char).is_alphanumeric() || string[k] == b'_' || string[k] == b'$') {
+        k += 1;
+    }
+    k = skip_whitespace(k);
+    if string.get(k) == Some(&b'<') {
+        let mut depth = 0;
+        while k < string.len() {
+            match string[k] {
+                b'<' => depth += 1,
+                b'>' if string[k - 1] != b'=' => depth -= 1,
+                _ => (),
+            }
+            k += 1;
+            if depth == 0 {
+                break;
+            }
+        }
+        k = skip_whitespace(k);
+    }
+    if string.get(k) != Some(&b'=') || string.get(k + 1) == Some(&b'=') {
+        return None;
+    }
+    let mut depth = 0;
+    let mut last = k;
+    k += 1;
+    while k < string.len() {
+        match string[k] as char {
+            '"' => k = string_lit_double_q(string, k).1,
+            '\'' => k = string_lit_single_q(string, k).1,
+            '`' => k = string_lit_backtick(string, k).1,
+            '/' if string.get(k + 1) == Some(&b'*') => k = block_comment(string, k).1,
+            '/' if string.get(k + 1) == Some(&b'/') => {
+                while k + 1 < string.len() && string[k + 1] != b'\n' {
+                    k += 1;
+                }
+            }
+            // A function type, whose `>` doesn't close a bracket.
+            '=' if string.get(k + 1) == Some(&b'>') => return None,
+            c if (c.is_alphabetic() || c == '_' || c == '$')
+                && !(string[k - 1].is_ascii_alphanumeric() || matches!(string[k - 1], b'_' | b'$')) =>
+            {
+                let start = k;
+                while string
+                    .get(k + 1)
+                    .is_some_and(|c| c.is_ascii_alphanumeric() || matches!(c, b'_' | b'$'))
+                {
+                    k += 1;
+                }
+                // A property may be named like a keyword, e.g. `{ in: string }`.
+                let key = matches!(string.get(skip_whitespace(k + 1)), Some(b':' | b'?'));
+                if !key
+                    && UNSUPPORTED_TYPE_WORDS
+                        .iter()
+                        .any(|w| w.as_bytes() == &string[start..=k])
+                {
+                    return None;
+                }
+            }
+            '{' | '(' | '[' | '<' => depth += 1,
+            '}' | ')' | ']' | '>' => depth -= 1,
+            ';' if depth == 0 => return Some(k),
+            '\n' if depth == 0 => {
+                // Without a semicolon the alias continues only if the line break is inside an operator chain.
+                let next = skip_whitespace(k);
+                if !matches!(string[last], b'=' | b'|' | b'&') && !matches!(string.get(next), Some(b'|' | b'&')) {
+                    return Some(last);
+                }
+            }
+            _ => (),
+        }
+        if k < string.len() && !is_skippable(&(string[k] as char)) {
+            last = k;
+        }
+        k += 1;
+    }
+    Some(last)
+}
+
+/// The tokens of `src`, each with the byte offset it starts at.
+pub fn tokenize(src: String) -> Vec<(Token, usize)> {
+    let mut tokens: Vec<(Token, usize)> = Vec::new();
+    let src_vec = Vec::from(src);
+    let src_vec_len = src_vec.len();
+    let mut i: usize = 0;
+
+    while i < src_vec_len {
+        let c: char = src_vec[i] as char;
+
+        if is_skippable(&c) {
+            i += 1;
+            continue;
+        }
+
+        let start = i;
+        let token = match c {
+            '/' => {
+                next! { src_vec, i,
+                    Token::Slash,
+                    '/' => {
+                        let mut j = i + 1;
+                        while j < src_vec_len && src_vec[j] as char != '\n' {
+                            j += 1;
+                        }
+                        i = j;
+                        Token::Comment
+                    },
+                    '*' => {
+                        let (start, end) = block_comment(&src_vec, i - 1);
+                        i = end;
+                        if src_vec.get(start + 2) == Some(&b'*') && end > start + 3 {
+                            Token::Doc(String::from_utf8_lossy(&src_vec[(start + 3)..(end - 1)]).to_string())
+                        } else {
+                            Token::Comment
+                        }
+                    },
+                }
+            }
+            '"' => {
+                let (start, end) = string_lit_double_q(&src_vec, i);
+                let string = String::from_utf8_lossy(&src_vec[(start + 1)..end]).to_string();
+                i = end;
+                Token::String(string)
+            }
+            '\'' => {
+                let (start, end) = string_lit_single_q(&src_vec, i);
+                let string = String::from_utf8_lossy(&src_vec[(start + 1)..end]).to_string();
+                i = end;
+                Token::String(string)
+            }
+            '&' => Token::Type(Type::Oper(Oper::And)),
+            '|' => Token::Type(Type::Oper(Oper::Or)),
+            '-' => Token::Dash,
+            '<' => Token::Type(Type::Punct(Punct::LAngleB)),
+            '>' => Token::Type(Type::Punct(Punct::RAngleB)),
+            '(' => Token::Type(Type::Punct(Punct::LPar)),
+            ')' => Token::Type(Type::Punct(Punct::RPar)),
+            '[' => Token::Type(Type::Punct(Punct::LBrack)),
+            ']' => Token::Type(Type::Punct(Punct::RBrack)),
+            '{' => Token::Type(Type::Punct(Punct::LBrace)),
+            '}' => Token::Type(Type::Punct(Punct::RBrace)),
+            '=' => Token::Eq,
+            ':' => Token::Colon,
+            ',' => Token::Comma,
+            '?' => Token::QM,
+            _ => {
+                if c.is_alphabetic() || c == '_' || c == '$' {
+                    let mut _c = c;
+                    let mut temp: String = String::new();
+                    let mut j: usize = i;
+                    // Qualified names such as `Temporal.Instant` are read as one identifier.
+                    while (!is_skippable(&_c) && _c.is_alphanumeric())
+                        || _c == '_'
+                        || _c == '$'
+                        || _c == '.' && src_vec.get(j + 1).is_some_and(|c| (*c as char).is_alphabetic())
+                    {
+                        temp += _c.to_string().as_str();
+                        j += 1;
+                        if j == src_vec_len {
+                            break;
+                        }
+                        _c = src_vec[j] as char;
+                    }
+                    i = j - 1;
+                    match temp.as_str() {
+                        "interface" => Token::Interface,
+                        "true" => Token::Type(Type::True),
+                        "false" => Token::Type(Type::False),
+                        "string" => Token::Type(Type::String),
+                        "number" => Token::Type(Type::Number),
+                        "object" => Token::Type(Type::Object),
+                        "boolean" => Token::Type(Type::Boolean),
+                        "null" => Token::Type(Type::Null),
+                        "undefined" => Token::Type(Type::Undefined),
+                        "symbol" => Token::Type(Type::Symbol),
+                        "bigint" => Token::Type(Type::BigInt),
+                        "any" => Token::Type(Type::Any),
+                        "unknown" => Token::Type(Type::Unknown),
+                        "Function" => Token::Type(Type::Function),
+                        _ => Token::Id(temp),
+                    }
+                } else if c.is_numeric() {
+                    let mut _c = c;
+                    let mut temp: String = String::new();
+                    let mut j: usize = i;
+                    while (!is_skippable(&_c) && _c.is_numeric()) || _c == '_' {
+                        temp += _c.to_string().as_str();
+                        j += 1;
+                        if j == src_vec_len {
+                            break;
+                        }
+                        _c = src_vec[j] as char;
+                    }
+                    i = j - 1;
+                    Token::Number(temp.replace("_", "").parse().unwrap())
+                } else {
+                    Token::Undefined(c.to_string())
+                }
+            }
+        };
+        tokens.push((token, start));
+        i += 1;
+    }
+    tokens.push((Token::EOF, src_vec_len));
+    tokens
+}
+
+fn string_lit_double_q(string: &[u8], index_of_quote: usize) -> (usize, usize) {
+    let mut j = index_of_quote + 1;
+    while j < string.len() && (string[j] as char != '"' || string[j - 1] as char == '\\') {
+        j += 1;
+    }
+    (index_of_quote, j)
+}
+
+fn string_lit_single_q(string: &[u8], index_of_quote: usize) -> (usize, usize) {
+    let mut j = index_of_quote + 1;
+    while j < string.len() && (string[j] as char != '\'' || string[j - 1] as char == '\\') {
+        j += 1;
+    }
+    (index_of_quote, j)
+}
+
+fn string_lit_backtick(string: &[u8], index_of_backtick: usize) -> (usize, usize) {
+    let mut j = index_of_backtick + 1;
+    while j < string.len() && (string[j] as char != '`' || string[j - 1] as char == '\\') {
+        j += 1;
+    }
+    (index_of_backtick, j)
+}
+
+fn block_comment(string: &[u8], index_of_slash: usize) -> (usize, usize) {
+    let mut j = index_of_slash + 2;
+    while j + 1 < string.len() && (string[j] as char != '*' || string[j + 1] as char != '/') {
+        j += 1;
+    }
+    (index_of_slash, (j + 1).min(string.len() - 1))
+}
@@ -1,45 +1,104 @@
 use rayon::prelude::{IntoParallelRefMutIterator, ParallelIterator};
 
-use crate::cmd::input;
-use crate::js::interfaces_to_js_string;
-use crate::lexer::{tokenize, find_interfaces};
+use crate::cmd::{input, Extension, Format, Options};
+use crate::js::{interfaces_to_js_files, interfaces_to_js_marked, interfaces_to_js_string};
+use crate::json_schema::{interfaces_to_json_schema, interfaces_to_openapi};
+use crate::lexer::{find_interfaces, tokenize};
 use crate::parsers::{
-    for_each_value, merge_interfaces, parse_and, parse_arrays, parse_custom_types, parse_generics, parse_interfaces,
-    parse_or, parse_parens, parse_tuples,
+    for_each_value, merge_interfaces, merge_intersections, parse_and, parse_arrays, parse_custom_types, parse_generics,
+    parse_interfaces, parse_or, parse_parens, parse_tuples, Key,
 };
+use crate::python::interfaces_to_python;
+use crate::rust::interfaces_to_rust;
+use crate::samples::{interfaces_to_tests, tests_path};
+use crate::source_map::source_map;
+use crate::zod::interfaces_to_zod;
 use std::fs;
 use std::time::Instant;
 
+mod case;
 mod cmd;
 mod js;
+mod jsdoc;
+mod json_schema;
 mod lexer;
 mod macros;
+mod optimize;
 mod parsers;
+mod pretty;
+mod python;
+mod rust;
+mod samples;
+mod source_map;
 mod tests;
+mod validate;
+mod zod;
 
 fn main() {
     let inst = Instant::now();
-    let (read_path, write_path, write_path_extension) = input();
-    let src = fs::read_to_string(read_path).unwrap();
-    let src = find_interfaces(src);
+    let options = input();
+    let source = fs::read_to_string(&options.read_path).unwrap();
+    let src = find_interfaces(source.clone());
     let tokens = tokenize(src);
     let mut interfaces = parse_interfaces(tokens);
-    let interfaces_clone = interfaces.clone();
 
+    interfaces
+        .par_iter_mut()
+        .for_each(|i| for_each_value(i, parse_generics));
+    let interfaces_clone = interfaces.clone();
     interfaces
         .par_iter_mut()
         .for_each(|i| parse_custom_types(i, &interfaces_clone));
-    interfaces.par_iter_mut().for_each(|i| for_each_value(i, parse_generics));
-    interfaces.par_iter_mut().for_each(|i| parse_tuples(i));
+    interfaces.retain(|i| !matches!(i.key, Key::Alias(_)));
+    interfaces.par_iter_mut().for_each(parse_tuples);
     interfaces.par_iter_mut().for_each(|i| for_each_value(i, parse_arrays));
-    interfaces.par_iter_mut().for_each(|i| parse_parens(i));
-    interfaces.par_iter_mut().for_each(|i| parse_and(i));
-    interfaces.par_iter_mut().for_each(|i| parse_or(i));
+    interfaces.par_iter_mut().for_each(parse_parens);
+    interfaces.par_iter_mut().for_each(parse_and);
+    interfaces.par_iter_mut().for_each(parse_or);
+    interfaces.par_iter_mut().for_each(merge_intersections);
     merge_interfaces(&mut interfaces);
+    let tests = options.emit_tests.then(|| interfaces_to_tests(&interfaces, &options));
+
+    if options.declarations {
+        let declarations = Options {
+            extension: Extension::DTS,
+            write_path: format!("{}.d.ts", options.write_path.trim_end_matches(".js")),
+            ..options.clone()
+        };
+        let string = interfaces_to_js_string(interfaces.clone(), &source, &declarations);
+        fs::write(&declarations.write_path, string).unwrap();
+    }
+    if options.out_dir {
+        fs::create_dir_all(&options.write_path).unwrap();
+        for (path, string) in interfaces_to_js_files(interfaces, &source, &options) {
+            fs::write(path, string).unwrap();
+        }
+    } else {
+        let string: String = match options.format {
+            Format::Guards if options.source_map => {
+                let (string, marks) = interfaces_to_js_marked(interfaces, &source, &options);
+                let (string, map) = source_map(&string, &marks, &source, &options);
+                fs::write(format!("{}.map", options.write_path), map).unwrap();
+                string
+            }
+            Format::Guards => interfaces_to_js_string(interfaces, &source, &options),
+            Format::Zod => interfaces_to_zod(&interfaces, &options),
+            Format::JsonSchema => interfaces_to_json_schema(&interfaces),
+            Format::Rust => interfaces_to_rust(&interfaces),
+            Format::TypedDict => interfaces_to_python(&interfaces, false),
+            Format::Pydantic => interfaces_to_python(&interfaces, true),
+            Format::OpenApi => interfaces_to_openapi(
+                &interfaces,
+                options.write_path.ends_with(".yaml") || options.write_path.ends_with(".yml"),
+            ),
+        };
 
-    let string: String = interfaces_to_js_string(interfaces, write_path_extension);
+        fs::write(&options.write_path, string).unwrap();
+    }
 
-    fs::write(write_path, string).unwrap();
+    if let Some(tests) = tests {
+        fs::write(tests_path(&options), tests).unwrap();
+    }
 
     println!("Finished Successfully in {}ms", inst.elapsed().as_millis());
 }
@@ -0,0 +1,231 @@
+use crate::js::{JSToken, JSType};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Or(Vec<Expr>),
+    And(Vec<Expr>),
+    Atom(Vec<JSToken>),
+    True,
+    False,
+}
+
+/// Rewrites the boolean expression produced by `js::to_js_token` into an equivalent, smaller one. Tokens that do
+/// not form a well-parenthesized `&&`/`||` expression are returned unchanged.
+pub fn optimize(tokens: Vec<JSToken>) -> Vec<JSToken> {
+    let mut i = 0;
+    let expr = parse_or(&tokens, &mut i);
+    if i != tokens.len() {
+        return tokens;
+    }
+    expr_to_tokens(&simplify(expr), false)
+}
+
+fn parse_or(tokens: &[JSToken], i: &mut usize) -> Expr {
+    let mut operands = vec![parse_and(tokens, i)];
+    while let Some(JSToken::Or) = tokens.get(*i) {
+        *i += 1;
+        operands.push(parse_and(tokens, i));
+    }
+    Expr::Or(operands)
+}
+
+fn parse_and(tokens: &[JSToken], i: &mut usize) -> Expr {
+    let mut operands = vec![parse_primary(tokens, i)];
+    while let Some(JSToken::And) = tokens.get(*i) {
+        *i += 1;
+        operands.push(parse_primary(tokens, i));
+    }
+    Expr::And(operands)
+}
+
+fn parse_primary(tokens: &[JSToken], i: &mut usize) -> Expr {
+    if let Some(JSToken::LPar) = tokens.get(*i) {
+        *i += 1;
+        let expr = parse_or(tokens, i);
+        if let Some(JSToken::RPar) = tokens.get(*i) {
+            *i += 1;
+        }
+        return expr;
+    }
+    let start = *i;
+    while let Some(t) = tokens.get(*i) {
+        if matches!(t, JSToken::And | JSToken::Or | JSToken::LPar | JSToken::RPar) {
+            break;
+        }
+        *i += 1;
+    }
+    match &tokens[start..*i] {
+        [] | [JSToken::None] | [JSToken::True] => Expr::True,
+        [JSToken::False] => Expr::False,
+        atom => Expr::Atom(atom.to_vec()),
+    }
+}
+
+pub fn simplify(expr: Expr) -> Expr {
+    match expr {
+        Expr::And(operands) => {
+            let mut flat: Vec<Expr> = Vec::new();
+            for operand in operands.into_iter().map(simplify) {
+                match operand {
+                    Expr::True => (),
+                    Expr::False => return Expr::False,
+                    Expr::And(inner) => inner.into_iter().for_each(|i| push_unique(&mut flat, i)),
+                    operand => push_unique(&mut flat, operand),
+                }
+            }
+            if contradicts(&flat) {
+                return Expr::False;
+            }
+            match flat.len() {
+                0 => Expr::True,
+                1 => flat.pop().unwrap(),
+                _ => Expr::And(flat),
+            }
+        }
+        Expr::Or(operands) => {
+            let mut flat: Vec<Expr> = Vec::new();
+            for operand in operands.into_iter().map(simplify) {
+                match operand {
+                    Expr::False => (),
+                    Expr::True => return Expr::True,
+                    Expr::Or(inner) => inner.into_iter().for_each(|i| push_unique(&mut flat, i)),
+                    operand => push_unique(&mut flat, operand),
+                }
+            }
+            if complements(&flat) {
+                return Expr::True;
+            }
+            match flat.len() {
+                0 => Expr::False,
+                1 => flat.pop().unwrap(),
+                _ => factor(flat),
+            }
+        }
+        expr => expr,
+    }
+}
+
+fn push_unique(operands: &mut Vec<Expr>, operand: Expr) {
+    if !operands.contains(&operand) {
+        operands.push(operand);
+    }
+}
+
+/// `a && b || a && c` becomes `a && (b || c)`, so checks shared by every union member run once.
+fn factor(operands: Vec<Expr>) -> Expr {
+    let conjunctions: Vec<Vec<Expr>> = operands
+        .iter()
+        .map(|i| match i {
+            Expr::And(inner) => inner.clone(),
+            i => vec![i.clone()],
+        })
+        .collect();
+    let mut prefix_len = 0;
+    while conjunctions
+        .iter()
+        .all(|c| c.len() > prefix_len && c[prefix_len] == conjunctions[0][prefix_len])
+    {
+        prefix_len += 1;
+    }
+    if prefix_len == 0 {
+        return Expr::Or(operands);
+    }
+    let prefix = conjunctions[0][..prefix_len].to_vec();
+    let rest = conjunctions
+        .into_iter()
+        .map(|c| Expr::And(c[prefix_len..].to_vec()))
+        .collect();
+    simplify(Expr::And([prefix, vec![Expr::Or(rest)]].concat()))
+}
+
+/// `typeof x === "a" && typeof x === "b"`, or `x === 1 && x === 2`.
+fn contradicts(operands: &[Expr]) -> bool {
+    let facts: Vec<(&JSToken, &JSToken)> = operands
+        .iter()
+        .filter_map(|i| match i {
+            Expr::Atom(atom) => match atom.as_slice() {
+                [JSToken::Typeof, addr, JSToken::EqEqEq, t @ JSToken::JSType(_)] => Some((addr, t)),
+                [addr @ JSToken::Addr(_), JSToken::EqEqEq, value] => Some((addr, value)),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect();
+    facts.iter().enumerate().any(|(i, (addr, fact))| {
+        facts[i + 1..].iter().any(|(other_addr, other_fact)| {
+            addr == other_addr
+                && (differs(fact, other_fact)
+                    || conflicts_with_typeof(fact, other_fact)
+                    || conflicts_with_typeof(other_fact, fact))
+        })
+    })
+}
+
+/// Whether two `typeof` results or two literals are known to be different values. Numbers are compared by value, so
+/// `1` and `1.0` or `0` and `-0` are the same, and strings only if neither has an escape sequence.
+fn differs(a: &JSToken, b: &JSToken) -> bool {
+    match (a, b) {
+        (JSToken::JSType(a), JSToken::JSType(b)) => a != b,
+        (JSToken::Number(a), JSToken::Number(b)) => match (a.parse::<f64>(), b.parse::<f64>()) {
+            (Ok(a), Ok(b)) => a != b,
+            _ => false,
+        },
+        (JSToken::String(a), JSToken::String(b)) => !a.contains('\\') && !b.contains('\\') && a != b,
+        (JSToken::True, JSToken::True) | (JSToken::False, JSToken::False) | (JSToken::Null, JSToken::Null) => false,
+        (
+            JSToken::String(_) | JSToken::Number(_) | JSToken::True | JSToken::False | JSToken::Null,
+            JSToken::String(_) | JSToken::Number(_) | JSToken::True | JSToken::False | JSToken::Null,
+        ) => true,
+        _ => false,
+    }
+}
+
+fn conflicts_with_typeof(value: &JSToken, js_type: &JSToken) -> bool {
+    let value_type = match value {
+        JSToken::String(_) => JSType::String,
+        JSToken::Number(_) => JSType::Number,
+        JSToken::True | JSToken::False => JSType::Boolean,
+        JSToken::Null => JSType::Object,
+        _ => return false,
+    };
+    matches!(js_type, JSToken::JSType(t) if *t != value_type)
+}
+
+/// `"k" in o === false || "k" in o`, which is what an optional `any` property reduces to.
+fn complements(operands: &[Expr]) -> bool {
+    operands.iter().any(|i| {
+        if let Expr::Atom(atom) = i {
+            if let [rest @ .., JSToken::EqEqEq, JSToken::False] = atom.as_slice() {
+                return operands.contains(&Expr::Atom(rest.to_vec()));
+            }
+        }
+        false
+    })
+}
+
+fn expr_to_tokens(expr: &Expr, in_and: bool) -> Vec<JSToken> {
+    match expr {
+        Expr::True => vec![JSToken::True],
+        Expr::False => vec![JSToken::False],
+        Expr::Atom(atom) => atom.clone(),
+        Expr::And(operands) => join(operands, JSToken::And, true),
+        Expr::Or(operands) if in_and => [
+            vec![JSToken::LPar],
+            join(operands, JSToken::Or, false),
+            vec![JSToken::RPar],
+        ]
+        .concat(),
+        Expr::Or(operands) => join(operands, JSToken::Or, false),
+    }
+}
+
+fn join(operands: &[Expr], separator: JSToken, in_and: bool) -> Vec<JSToken> {
+    let mut tokens = Vec::new();
+    for (i, operand) in operands.iter().enumerate() {
+        if i > 0 {
+            tokens.push(separator.clone());
+        }
+        tokens.extend(expr_to_tokens(operand, in_and));
+    }
+    tokens
+}
@@ -1,602 +1,1378 @@
-use core::panic;
-use std::vec;
-
-use rayon::prelude::{IntoParallelIterator, ParallelIterator};
-
-use crate::lexer::{Oper, Punct, Token, Type};
-
-#[derive(Debug, Clone, PartialEq)]
-pub enum Value {
-    Entry(Entry),
-    Type(Type),
-}
-
-#[derive(Debug, Clone, PartialEq)]
-pub enum Key {
-    Name(String),
-    Optional(String),
-    Generic(Generic),
-    Paren,
-    Or,
-    And,
-    None,
-    Tuple,
-}
-
-#[derive(Debug, Clone, PartialEq)]
-pub enum Generic {
-    Custom(String),
-    Array,
-}
-
-#[derive(Debug, Clone, PartialEq)]
-pub struct Entry {
-    pub key: Key,
-    pub value: Vec<Value>,
-}
-
-fn stack_handle_remove(stack: &mut Vec<Entry>) -> () {
-    let stack_len = stack.len();
-    if stack_len < 2 {
-        return;
-    }
-    let last = stack[stack_len - 1].clone();
-    stack[stack_len - 2].value.push(Value::Entry(last));
-    stack.pop();
-}
-
-fn add_type_value_to_last(stack: &mut Vec<Entry>, _type: &Type) -> () {
-    let stack_len = stack.len();
-    stack[stack_len - 1].value.push(Value::Type(_type.clone()));
-}
-
-pub fn parse_interfaces(mut tokens: Vec<Token>) -> Vec<Entry> {
-    let mut i = 0;
-    while i < tokens.len() - 1 {
-        if let Token::Colon | Token::QM = tokens[i + 1] {
-            let mut has_matched = true;
-            match &tokens[i] {
-                Token::Id(str) | Token::String(str) => tokens[i] = Token::Key(str.to_string()),
-                Token::Type(t) => match t {
-                    Type::True => tokens[i] = Token::Key("true".to_string()),
-                    Type::False => tokens[i] = Token::Key("false".to_string()),
-                    Type::String => tokens[i] = Token::Key("string".to_string()),
-                    Type::Number => tokens[i] = Token::Key("number".to_string()),
-                    Type::Object => tokens[i] = Token::Key("object".to_string()),
-                    Type::Boolean => tokens[i] = Token::Key("boolean".to_string()),
-                    Type::Null => tokens[i] = Token::Key("null".to_string()),
-                    Type::Undefined => tokens[i] = Token::Key("undefined".to_string()),
-                    Type::Symbol => tokens[i] = Token::Key("symbol".to_string()),
-                    Type::BigInt => tokens[i] = Token::Key("bigint".to_string()),
-                    Type::Any => tokens[i] = Token::Key("any".to_string()),
-                    Type::Unknown => tokens[i] = Token::Key("unknown".to_string()),
-                    Type::Function => tokens[i] = Token::Key("Function".to_string()),
-                    _ => has_matched = false,
-                },
-                Token::Interface => tokens[i] = Token::Key("interface".to_string()),
-                _ => has_matched = false,
-            }
-            if has_matched {
-                tokens.insert(i, Token::EOE);
-                i += 1;
-            }
-        }
-        i += 1;
-    }
-
-    // REMOVE UNKOWN AND ANY: 
-    // tokens = tokens
-    //     .into_par_iter()
-    //     .filter(|i| {
-    //         if let Token::Type(Type::Any) | Token::Type(Type::Unknown) = i {
-    //             false
-    //         } else {
-    //             true
-    //         }
-    //     })
-    //     .collect();
-    // let mut i = 0;
-    // while i < tokens.len() - 1 {
-    //     if let Token::Type(Type::Oper(Oper::Or)) | Token::Type(Type::Oper(Oper::And)) = tokens[i] {
-    //         if let Token::Type(Type::Oper(Oper::Or)) | Token::Type(Type::Oper(Oper::And)) = tokens[i + 1] {
-    //             tokens.remove(i);
-    //             if i > 0 {
-    //                 i -= 1;
-    //             }
-    //         }
-    //     }
-    //     i += 1;
-    // }
-
-    let mut i = 1;
-    while i < tokens.len() {
-        match tokens[i] {
-            Token::Number(n) => {
-                if let Token::Dash = tokens[i - 1] {
-                    tokens.splice(
-                        (i - 1)..=i,
-                        [Token::Type(Type::NumberLit("-".to_string() + n.to_string().as_str()))],
-                    );
-                    i -= 1;
-                } else {
-                    tokens[i] = Token::Type(Type::NumberLit(n.to_string()));
-                }
-            }
-            _ => (),
-        }
-        i += 1;
-    }
-
-    tokens = tokens
-        .into_par_iter()
-        .filter(|i| if let Token::Undefined(_) = i { false } else { true })
-        .collect();
-
-    let mut i: usize = 0;
-    while i < tokens.len() - 1 {
-        match tokens[i] {
-            Token::Type(Type::Punct(Punct::LBrace)) => {
-                if let Token::EOE = tokens[i + 1] {
-                    tokens.remove(i + 1);
-                    i -= 1;
-                }
-            }
-            Token::Type(Type::Punct(Punct::RBrace)) => {
-                tokens.insert(i, Token::Type(Type::Punct(Punct::RBrace)));
-                i += 1;
-            }
-            _ => (),
-        }
-        i += 1;
-    }
-
-    tokens = tokens
-        .into_par_iter()
-        .map(|i| {
-            if let Token::Type(Type::Punct(Punct::RBrace)) = i {
-                Token::EOE
-            } else {
-                i
-            }
-        })
-        .collect();
-
-    let mut i: usize = 0;
-    let mut stack: Vec<Entry> = Vec::new();
-    let mut interfaces: Vec<Entry> = Vec::new();
-    while i < tokens.len() {
-        match &tokens[i] {
-            Token::Interface => {
-                if stack.len() == 0 {
-                    let entry_name = match &tokens[i + 1] {
-                        Token::Id(str) => str.to_string(),
-                        _ => panic!("Interface name not found"),
-                    };
-                    let entry = Entry {
-                        key: Key::Name(entry_name),
-                        value: Vec::new(),
-                    };
-                    stack.push(entry);
-                    i += 2;
-                } else if stack.len() == 1 {
-                    interfaces.push(stack[0].clone());
-                    stack.pop();
-                    let entry_name = match &tokens[i + 1] {
-                        Token::Id(str) => str.to_string(),
-                        _ => panic!("Interface name not found"),
-                    };
-                    let entry = Entry {
-                        key: Key::Name(entry_name),
-                        value: Vec::new(),
-                    };
-                    stack.push(entry);
-                    i += 2;
-                } else {
-                    panic!("stack length > 1");
-                }
-            }
-            Token::Key(str) => {
-                if let Some(Token::QM) = tokens.get(i + 1) {
-                    stack.push(Entry {
-                        key: Key::Optional(str.to_string()),
-                        value: Vec::new(),
-                    });
-                    i += 1;
-                } else {
-                    stack.push(Entry {
-                        key: Key::Name(str.to_string()),
-                        value: Vec::new(),
-                    });
-                };
-            }
-            Token::Type(Type::Punct(Punct::LBrace)) => {
-                let entry = Entry {
-                    key: Key::None,
-                    value: Vec::new(),
-                };
-                stack.push(entry);
-            }
-            Token::EOE => {
-                stack_handle_remove(&mut stack);
-            }
-            Token::Type(_type) => add_type_value_to_last(&mut stack, _type),
-            Token::String(str) => add_type_value_to_last(&mut stack, &Type::StringLit(str.clone())),
-            Token::Id(id) => add_type_value_to_last(&mut stack, &Type::Custom(id.to_string())),
-            _ => (),
-        }
-        i += 1;
-    }
-
-    if stack.len() == 1 {
-        interfaces.push(stack[0].clone());
-        stack.pop();
-    } else {
-        panic!("stack length != 1");
-    }
-
-    interfaces
-}
-
-pub fn parse_arrays(value: &mut Vec<Value>) -> () {
-    if value.len() == 0 {
-        return;
-    }
-    let mut i = value.len() - 1;
-    while i > 0 {
-        if let (&Value::Type(Type::Punct(Punct::LBrack)), &Value::Type(Type::Punct(Punct::RBrack))) =
-            (&value[i - 1], &value[i])
-        {
-            let end = i;
-            let start: usize;
-            let mut j = i - 2;
-            let mut par_count = 0;
-            let mut args: Vec<Value> = Vec::new();
-            loop {
-                match &value[j] {
-                    Value::Type(Type::Punct(Punct::RBrack)) => {
-                        args.push(Value::Type(Type::Punct(Punct::RBrack)));
-                        j -= 1;
-                        continue;
-                    }
-                    Value::Type(Type::Punct(Punct::LBrack)) => {
-                        args.push(Value::Type(Type::Punct(Punct::LBrack)));
-                        j -= 1;
-                        continue;
-                    }
-                    Value::Type(Type::Punct(Punct::RPar)) => par_count += 1,
-                    Value::Type(Type::Punct(Punct::LPar)) => {
-                        par_count -= 1;
-                        if par_count == 0 && args.len() > 0 {
-                            start = j;
-                            args.reverse();
-                            value.splice(
-                                start..=end,
-                                [Value::Entry(Entry {
-                                    key: Key::Generic(Generic::Array),
-                                    value: args,
-                                })],
-                            );
-                            if let Value::Entry(e) = &mut value[start] {
-                                parse_arrays(&mut e.value);
-                            }
-                            if start == 0 {
-                                i = start;
-                            } else {
-                                i = start - 1;
-                            }
-                            break;
-                        } else if par_count == 0 && args.len() == 0 {
-                            panic!("unexpected Value before []");
-                        }
-                    }
-                    t => {
-                        args.push(t.clone());
-                        if par_count == 0 && args.len() > 0 {
-                            start = j;
-                            args.reverse();
-                            value.splice(
-                                start..=end,
-                                [Value::Entry(Entry {
-                                    key: Key::Generic(Generic::Array),
-                                    value: args,
-                                })],
-                            );
-                            if let Value::Entry(e) = &mut value[start] {
-                                parse_arrays(&mut e.value);
-                            }
-                            if start == 0 {
-                                i = start;
-                            } else {
-                                i = start - 1;
-                            }
-                            break;
-                        } else if par_count == 0 && args.len() == 0 {
-                            panic!("unexpected Value before []");
-                        }
-                    }
-                }
-                j -= 1;
-            }
-        } else {
-            i -= 1;
-        }
-    }
-}
-
-pub fn parse_generics(value: &mut Vec<Value>) -> () {
-    let mut i = 1;
-    while i < value.len() {
-        if let &Value::Type(Type::Punct(Punct::LAngleB)) = &value[i] {
-            let generic_name: Generic = match &value[i - 1] {
-                Value::Type(Type::Custom(str)) => match str.clone().as_str() {
-                    "Array" => Generic::Array,
-                    _ => Generic::Custom(str.clone()),
-                },
-                _ => panic!("Unexpected generic name"),
-            };
-            let start = i - 1;
-            let end: usize;
-            let mut j = i + 1;
-            let mut args: Vec<Value> = Vec::new();
-            let mut count = 1;
-            while j < value.len() {
-                match &value[j] {
-                    Value::Type(Type::Punct(Punct::LAngleB)) => {
-                        args.push(Value::Type(Type::Punct(Punct::LAngleB)));
-                        count += 1;
-                    }
-                    Value::Type(Type::Punct(Punct::RAngleB)) => {
-                        count -= 1;
-                        if count == 0 && args.len() > 0 {
-                            end = j;
-                            value.splice(
-                                start..=end,
-                                [Value::Entry(Entry {
-                                    key: Key::Generic(generic_name),
-                                    value: args.clone(),
-                                })],
-                            );
-                            if let Value::Entry(e) = &mut value[start] {
-                                parse_generics(&mut e.value);
-                            }
-                            break;
-                        } else if count == 0 && args.len() == 0 {
-                            panic!("why?");
-                        }
-                        args.push(Value::Type(Type::Punct(Punct::RAngleB)));
-                    }
-                    t => {
-                        args.push(t.clone());
-                    }
-                }
-                j += 1;
-            }
-        }
-        i += 1;
-    }
-}
-
-pub fn for_each_value(entry: &mut Entry, f: fn(value: &mut Vec<Value>)) {
-    (f)(&mut entry.value);
-    for j in &mut entry.value {
-        if let Value::Entry(e) = j {
-            for_each_value(e, f);
-        }
-    }
-}
-
-pub fn parse_parens(entry: &mut Entry) {
-    let mut i = 0;
-    while i < entry.value.len() {
-        match &mut entry.value[i] {
-            Value::Entry(e) => {
-                parse_parens(e);
-            }
-            Value::Type(t) => match t {
-                Type::Punct(Punct::LPar) => {
-                    let start = i;
-                    let end: usize;
-                    let mut j = i + 1;
-                    let mut par_count = 1;
-                    let mut value: Vec<Value> = Vec::new();
-                    while j < entry.value.len() {
-                        match &mut entry.value[j] {
-                            Value::Type(Type::Punct(Punct::LPar)) => {
-                                par_count += 1;
-                                value.push(Value::Type(Type::Punct(Punct::LPar)));
-                            }
-                            Value::Type(Type::Punct(Punct::RPar)) => {
-                                par_count -= 1;
-                                if par_count == 0 {
-                                    end = j;
-                                    entry
-                                        .value
-                                        .splice(start..=end, [Value::Entry(Entry { key: Key::Paren, value })]);
-                                    if let Value::Entry(e) = &mut entry.value[i] {
-                                        parse_parens(e);
-                                    }
-                                    break;
-                                } else {
-                                    value.push(Value::Type(Type::Punct(Punct::RPar)));
-                                }
-                            }
-                            t => {
-                                value.push(t.clone());
-                            }
-                        }
-                        j += 1;
-                    }
-                }
-                _ => (),
-            },
-        }
-        i += 1;
-    }
-}
-
-pub fn parse_tuples(entry: &mut Entry) {
-    let mut i = 0;
-    while i < entry.value.len() {
-        match &mut entry.value[i] {
-            Value::Entry(e) => {
-                parse_tuples(e);
-            }
-            Value::Type(t) => match t {
-                Type::Punct(Punct::LBrack) => {
-                    if let Value::Type(Type::Punct(Punct::RBrack)) = entry.value[i + 1] {
-                        i += 1;
-                        continue;
-                    }
-                    let start = i;
-                    let end: usize;
-                    let mut j = i + 1;
-                    let mut brack_count = 1;
-                    let mut value: Vec<Value> = Vec::new();
-                    while j < entry.value.len() {
-                        match &mut entry.value[j] {
-                            Value::Type(Type::Punct(Punct::LBrack)) => {
-                                brack_count += 1;
-                                value.push(Value::Type(Type::Punct(Punct::LBrack)));
-                            }
-                            Value::Type(Type::Punct(Punct::RBrack)) => {
-                                brack_count -= 1;
-                                if brack_count == 0 {
-                                    end = j;
-                                    entry
-                                        .value
-                                        .splice(start..=end, [Value::Entry(Entry { key: Key::Tuple, value })]);
-                                    if let Value::Entry(e) = &mut entry.value[i] {
-                                        parse_tuples(e);
-                                    }
-                                    break;
-                                } else {
-                                    value.push(Value::Type(Type::Punct(Punct::RBrack)));
-                                }
-                            }
-                            t => {
-                                value.push(t.clone());
-                            }
-                        }
-                        j += 1;
-                    }
-                }
-                _ => (),
-            },
-        }
-        i += 1;
-    }
-}
-
-pub fn parse_and(entry: &mut Entry) {
-    let mut i = 0;
-    while i < entry.value.len() {
-        match &mut entry.value[i] {
-            Value::Entry(e) => {
-                parse_and(e);
-                i += 1;
-            }
-            Value::Type(Type::Oper(Oper::And)) => {
-                if i == 0 || i == entry.value.len() - 1 {
-                    entry.value.remove(i);
-                } else {
-                    entry.value.splice(
-                        (i - 1)..=(i + 1),
-                        [Value::Entry(Entry {
-                            key: Key::And,
-                            value: vec![entry.value[i - 1].clone(), entry.value[i + 1].clone()],
-                        })],
-                    );
-                    i -= 1;
-                }
-            }
-            _ => i += 1,
-        }
-    }
-}
-
-pub fn parse_or(entry: &mut Entry) {
-    let mut i = 0;
-    while i < entry.value.len() {
-        match &mut entry.value[i] {
-            Value::Entry(e) => {
-                parse_or(e);
-                i += 1;
-            }
-            Value::Type(Type::Oper(Oper::Or)) => {
-                if i == 0 || i == entry.value.len() - 1 {
-                    entry.value.remove(i);
-                } else {
-                    entry.value.splice(
-                        (i - 1)..=(i + 1),
-                        [Value::Entry(Entry {
-                            key: Key::Or,
-                            value: vec![entry.value[i - 1].clone(), entry.value[i + 1].clone()],
-                        })],
-                    );
-                    i -= 1;
-                }
-            }
-            _ => i += 1,
-        }
-    }
-}
-
-pub fn parse_custom_types(entry: &mut Entry, interfaces: &Vec<Entry>) {
-    let mut i = 0;
-    while i < entry.value.len() {
-        let current = &mut entry.value[i];
-        match current {
-            Value::Entry(e) => {
-                parse_custom_types(e, interfaces);
-            }
-            Value::Type(Type::Custom(custom_type)) => {
-                let custom_type_clone = custom_type.clone();
-                for j in interfaces {
-                    if let Key::Name(interface_name) = &j.key {
-                        if interface_name.clone() == custom_type_clone {
-                            let new = Value::Entry(Entry {
-                                key: Key::None,
-                                value: j.value.clone(),
-                            })
-                            .clone();
-                            *current = new;
-                            if let Value::Entry(e) = current {
-                                parse_custom_types(e, interfaces);
-                            }
-                        }
-                    }
-                }
-            }
-            _ => (),
-        }
-        i += 1;
-    }
-}
-
-pub fn merge_interfaces(interfaces: &mut Vec<Entry>) {
-    let mut i = 0;
-    while i < interfaces.len() {
-        let current_name = if let Key::Name(str) = &interfaces[i].key {
-            str.clone()
-        } else {
-            unreachable!()
-        };
-        let mut j = i + 1;
-        while j < interfaces.len() {
-            if let Key::Name(str) = &interfaces[j].key {
-                if str.clone() == current_name {
-                    for k in 0..interfaces[j].value.len() {
-                        let clone = interfaces[j].clone().value[k].clone();
-                        interfaces[i].value.push(clone);
-                    }
-                    interfaces.remove(j);
-                    j -= 1;
-                }
-            } else {
-                unreachable!()
-            }
-            j += 1;
-        }
-        i += 1;
-    }
-}
+use core::panic;
+use std::collections::HashMap;
+use std::vec;
+
+use rayon::prelude::{IntoParallelIterator, ParallelIterator};
+
+use crate::{
+    jsdoc::{parse_doc, Doc},
+    lexer::{Oper, Punct, Token, Type},
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Entry(Entry),
+    Type(Type),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Key {
+    Name(String),
+    /// A `type Name = ...` declaration, inlined wherever `Name` is used.
+    Alias(String),
+    /// A use of the interface or alias `Name`, holding its inlined declaration. Empty if it refers back to a
+    /// declaration that is already being inlined, i.e. the type is recursive.
+    Ref(String),
+    Optional(String),
+    Generic(Generic),
+    Paren,
+    Or,
+    And,
+    None,
+    Tuple,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Generic {
+    Custom(String),
+    /// A generic interface used inside its own declaration, e.g. `Tree<T>` in `interface Tree<T>`. Its value holds
+    /// the type arguments, each in parentheses. It is checked by calling its guard instead of being inlined.
+    Ref(String),
+    Array,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub key: Key,
+    pub value: Vec<Value>,
+    pub doc: Option<Doc>,
+    /// Type parameters of a generic interface, e.g. `T` in `interface Page<T>`.
+    pub params: Vec<String>,
+    pub span: Span,
+}
+
+/// Byte offset of the declaration of an interface, alias or property in the input file.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Span(pub Option<usize>);
+
+fn stack_handle_remove(stack: &mut Vec<Entry>) {
+    let stack_len = stack.len();
+    if stack_len < 2 {
+        return;
+    }
+    let last = stack[stack_len - 1].clone();
+    stack[stack_len - 2].value.push(Value::Entry(last));
+    stack.pop();
+}
+
+fn add_type_value_to_last(stack: &mut [Entry], _type: &Type) {
+    let stack_len = stack.len();
+    stack[stack_len - 1].value.push(Value::Type(_type.clone()));
+}
+
+pub fn parse_interfaces(mut tokens: Vec<(Token, usize)>) -> Vec<Entry> {
+    // `readonly` doesn't change what is checked at runtime, unless it's the name of a property.
+    let mut i = 0;
+    while i < tokens.len() - 1 {
+        if tokens[i].0 == Token::Id("readonly".to_string()) && !matches!(tokens[i + 1].0, Token::Colon | Token::QM) {
+            tokens.remove(i);
+        } else {
+            i += 1;
+        }
+    }
+
+    let mut i = 0;
+    while i < tokens.len() - 1 {
+        if let Token::Colon | Token::QM = tokens[i + 1].0 {
+            let mut has_matched = true;
+            match &tokens[i].0 {
+                Token::Id(str) | Token::String(str) => tokens[i].0 = Token::Key(str.to_string()),
+                Token::Type(t) => match t {
+                    Type::True => tokens[i].0 = Token::Key("true".to_string()),
+                    Type::False => tokens[i].0 = Token::Key("false".to_string()),
+                    Type::String => tokens[i].0 = Token::Key("string".to_string()),
+                    Type::Number => tokens[i].0 = Token::Key("number".to_string()),
+                    Type::Object => tokens[i].0 = Token::Key("object".to_string()),
+                    Type::Boolean => tokens[i].0 = Token::Key("boolean".to_string()),
+                    Type::Null => tokens[i].0 = Token::Key("null".to_string()),
+                    Type::Undefined => tokens[i].0 = Token::Key("undefined".to_string()),
+                    Type::Symbol => tokens[i].0 = Token::Key("symbol".to_string()),
+                    Type::BigInt => tokens[i].0 = Token::Key("bigint".to_string()),
+                    Type::Any => tokens[i].0 = Token::Key("any".to_string()),
+                    Type::Unknown => tokens[i].0 = Token::Key("unknown".to_string()),
+                    Type::Function => tokens[i].0 = Token::Key("Function".to_string()),
+                    _ => has_matched = false,
+                },
+                Token::Interface => tokens[i].0 = Token::Key("interface".to_string()),
+                _ => has_matched = false,
+            }
+            if has_matched {
+                // Comments that precede the key belong to its entry, the previous one ends before them.
+                let mut j = i;
+                while j > 0 && matches!(tokens[j - 1].0, Token::Doc(_) | Token::Comment) {
+                    j -= 1;
+                }
+                tokens.insert(j, (Token::EOE, tokens[j].1));
+                i += 1;
+            }
+        }
+        i += 1;
+    }
+
+    // REMOVE UNKOWN AND ANY:
+    // tokens = tokens
+    //     .into_par_iter()
+    //     .filter(|i| {
+    //         if let Token::Type(Type::Any) | Token::Type(Type::Unknown) = i {
+    //             false
+    //         } else {
+    //             true
+    //         }
+    //     })
+    //     .collect();
+    // let mut i = 0;
+    // while i < tokens.len() - 1 {
+    //     if let Token::Type(Type::Oper(Oper::Or)) | Token::Type(Type::Oper(Oper::And)) = tokens[i] {
+    //         if let Token::Type(Type::Oper(Oper::Or)) | Token::Type(Type::Oper(Oper::And)) = tokens[i + 1] {
+    //             tokens.remove(i);
+    //             if i > 0 {
+    //                 i -= 1;
+    //             }
+    //         }
+    //     }
+    //     i += 1;
+    // }
+
+    let mut i = 1;
+    while i < tokens.len() {
+        if let Token::Number(n) = tokens[i].0 {
+            if let Token::Dash = tokens[i - 1].0 {
+                tokens.splice(
+                    (i - 1)..=i,
+                    [(
+                        Token::Type(Type::NumberLit("-".to_string() + n.to_string().as_str())),
+                        tokens[i - 1].1,
+                    )],
+                );
+                i -= 1;
+            } else {
+                tokens[i].0 = Token::Type(Type::NumberLit(n.to_string()));
+            }
+        }
+        i += 1;
+    }
+
+    // Commas only matter between type arguments, e.g. `Record<K, V>`.
+    let mut angle_depth = 0;
+    tokens = tokens
+        .into_iter()
+        .filter_map(|(i, offset)| match i {
+            Token::Type(Type::Punct(Punct::LAngleB)) => {
+                angle_depth += 1;
+                Some((i, offset))
+            }
+            Token::Type(Type::Punct(Punct::RAngleB)) => {
+                angle_depth -= 1;
+                Some((i, offset))
+            }
+            Token::Comma if angle_depth > 0 => Some((Token::Type(Type::Punct(Punct::Comma)), offset)),
+            Token::Comma | Token::Undefined(_) => None,
+            i => Some((i, offset)),
+        })
+        .collect();
+
+    let mut i: usize = 0;
+    while i < tokens.len() - 1 {
+        match tokens[i].0 {
+            Token::Type(Type::Punct(Punct::LBrace)) => {
+                if let Token::EOE = tokens[i + 1].0 {
+                    tokens.remove(i + 1);
+                    i -= 1;
+                }
+            }
+            Token::Type(Type::Punct(Punct::RBrace)) => {
+                tokens.insert(i, (Token::Type(Type::Punct(Punct::RBrace)), tokens[i].1));
+                i += 1;
+            }
+            _ => (),
+        }
+        i += 1;
+    }
+
+    let (tokens, offsets): (Vec<Token>, Vec<usize>) = tokens
+        .into_par_iter()
+        .map(|(i, offset)| {
+            if let Token::Type(Type::Punct(Punct::RBrace)) = i {
+                (Token::EOE, offset)
+            } else {
+                (i, offset)
+            }
+        })
+        .unzip();
+    let (tokens, offsets) = fill_type_defaults(tokens, offsets);
+
+    let mut i: usize = 0;
+    let mut stack: Vec<Entry> = Vec::new();
+    let mut interfaces: Vec<Entry> = Vec::new();
+    let mut doc: Option<Doc> = None;
+    while i < tokens.len() {
+        match &tokens[i] {
+            Token::Interface => {
+                if stack.is_empty() {
+                    let entry_name = match &tokens[i + 1] {
+                        Token::Id(str) => str.to_string(),
+                        _ => panic!("Interface name not found"),
+                    };
+                    let (params, _, end) = type_params(&tokens, i + 2);
+                    let entry = Entry {
+                        key: Key::Name(entry_name),
+                        value: Vec::new(),
+                        doc: doc.take(),
+                        params,
+                        span: Span(Some(offsets[i])),
+                    };
+                    stack.push(entry);
+                    i = end;
+                } else if stack.len() == 1 {
+                    interfaces.push(stack[0].clone());
+                    stack.pop();
+                    let entry_name = match &tokens[i + 1] {
+                        Token::Id(str) => str.to_string(),
+                        _ => panic!("Interface name not found"),
+                    };
+                    let (params, _, end) = type_params(&tokens, i + 2);
+                    let entry = Entry {
+                        key: Key::Name(entry_name),
+                        value: Vec::new(),
+                        doc: doc.take(),
+                        params,
+                        span: Span(Some(offsets[i])),
+                    };
+                    stack.push(entry);
+                    i = end;
+                } else {
+                    panic!("stack length > 1");
+                }
+            }
+            Token::Id(id) if id == "type" && stack.len() <= 1 && matches!(tokens.get(i + 1), Some(Token::Id(_))) => {
+                if let Some(entry) = stack.pop() {
+                    interfaces.push(entry);
+                }
+                let alias_name = match &tokens[i + 1] {
+                    Token::Id(str) => str.to_string(),
+                    _ => unreachable!(),
+                };
+                let (params, _, end) = type_params(&tokens, i + 2);
+                stack.push(Entry {
+                    key: Key::Alias(alias_name),
+                    value: Vec::new(),
+                    doc: doc.take(),
+                    params,
+                    span: Span(Some(offsets[i])),
+                });
+                i = end;
+            }
+            Token::Key(str) => {
+                if let Some(Token::QM) = tokens.get(i + 1) {
+                    stack.push(Entry {
+                        key: Key::Optional(str.to_string()),
+                        value: Vec::new(),
+                        doc: doc.take(),
+                        params: Vec::new(),
+                        span: Span(Some(offsets[i])),
+                    });
+                    i += 1;
+                } else {
+                    stack.push(Entry {
+                        key: Key::Name(str.to_string()),
+                        value: Vec::new(),
+                        doc: doc.take(),
+                        params: Vec::new(),
+                        span: Span(Some(offsets[i])),
+                    });
+                };
+            }
+            Token::Type(Type::Punct(Punct::LBrace)) => {
+                let entry = Entry {
+                    key: Key::None,
+                    value: Vec::new(),
+                    doc: None,
+                    params: Vec::new(),
+                    span: Span(Some(offsets[i])),
+                };
+                stack.push(entry);
+            }
+            Token::EOE => {
+                doc = None;
+                stack_handle_remove(&mut stack);
+            }
+            Token::Doc(str) => doc = Some(parse_doc(str)),
+            Token::Type(_type) => add_type_value_to_last(&mut stack, _type),
+            Token::String(str) => add_type_value_to_last(&mut stack, &Type::StringLit(str.clone())),
+            Token::Id(id) if stack.first().is_some_and(|i| i.params.contains(id)) => {
+                add_type_value_to_last(&mut stack, &Type::Param(id.to_string()))
+            }
+            Token::Id(id) => add_type_value_to_last(&mut stack, &Type::Custom(id.to_string())),
+            _ => (),
+        }
+        i += 1;
+    }
+
+    if stack.len() == 1 {
+        interfaces.push(stack[0].clone());
+        stack.pop();
+    } else {
+        panic!("stack length != 1");
+    }
+
+    interfaces
+}
+
+/// The tokens of the default of each type parameter, if it has one.
+type Defaults = Vec<Option<Vec<Token>>>;
+
+/// Reads `<T, U extends X = Y>` after an interface name. Returns the parameter names, their defaults and the index of
+/// the token before the interface body.
+fn type_params(tokens: &[Token], start: usize) -> (Vec<String>, Defaults, usize) {
+    let mut params = Vec::new();
+    let mut defaults: Defaults = Vec::new();
+    if tokens.get(start) != Some(&Token::Type(Type::Punct(Punct::LAngleB))) {
+        return (params, defaults, start);
+    }
+    let mut depth = 0;
+    let mut expects_name = true;
+    for (i, token) in tokens.iter().enumerate().skip(start) {
+        match token {
+            Token::Type(Type::Punct(Punct::LAngleB)) => depth += 1,
+            Token::Type(Type::Punct(Punct::RAngleB)) => {
+                depth -= 1;
+                if depth == 0 {
+                    return (params, defaults, i + 1);
+                }
+            }
+            Token::Type(Type::Punct(Punct::Comma)) if depth == 1 => {
+                expects_name = true;
+                continue;
+            }
+            Token::Id(id) if depth == 1 && expects_name => {
+                params.push(id.clone());
+                defaults.push(None);
+                expects_name = false;
+                continue;
+            }
+            Token::Eq if depth == 1 => {
+                if let Some(default) = defaults.last_mut() {
+                    *default = Some(Vec::new());
+                }
+                continue;
+            }
+            _ => (),
+        }
+        if let Some(Some(default)) = defaults.last_mut() {
+            default.push(token.clone());
+        }
+    }
+    panic!("Unclosed type parameter list");
+}
+
+/// Reads `<A, B>` after the name of a generic type where it is used. Returns the tokens of each argument and the index
+/// after the `>`, or no arguments and `start` without a `<`.
+fn type_args_at(tokens: &[Token], start: usize) -> (Vec<Vec<Token>>, usize) {
+    if tokens.get(start) != Some(&Token::Type(Type::Punct(Punct::LAngleB))) {
+        return (Vec::new(), start);
+    }
+    let mut args = vec![Vec::new()];
+    let mut depth = 0;
+    for (i, token) in tokens.iter().enumerate().skip(start) {
+        match token {
+            Token::Type(Type::Punct(Punct::LAngleB)) => {
+                depth += 1;
+                if depth == 1 {
+                    continue;
+                }
+            }
+            Token::Type(Type::Punct(Punct::RAngleB)) => {
+                depth -= 1;
+                if depth == 0 {
+                    return (args, i + 1);
+                }
+            }
+            Token::Type(Type::Punct(Punct::Comma)) if depth == 1 => {
+                args.push(Vec::new());
+                continue;
+            }
+            _ => (),
+        }
+        args.last_mut().unwrap().push(token.clone());
+    }
+    panic!("Unclosed type argument list");
+}
+
+/// Writes out the defaults of the type parameters left out where a generic interface or alias is used, so
+/// `interface Page<T = string>` makes `Page` read as `Page<string>`. A default may use the parameters before it.
+fn fill_type_defaults(mut tokens: Vec<Token>, mut offsets: Vec<usize>) -> (Vec<Token>, Vec<usize>) {
+    let mut declarations: HashMap<String, (Vec<String>, Defaults)> = HashMap::new();
+    for i in 0..tokens.len() {
+        if let (true, Some(Token::Id(name))) = (declares(&tokens, i), tokens.get(i + 1)) {
+            let (params, defaults, _) = type_params(&tokens, i + 2);
+            if defaults.iter().any(Option::is_some) {
+                declarations.insert(name.clone(), (params, defaults));
+            }
+        }
+    }
+    let mut i = 0;
+    while i < tokens.len() {
+        let declaration = match &tokens[i] {
+            Token::Id(name) if i == 0 || !declares(&tokens, i - 1) => declarations.get(name),
+            _ => None,
+        };
+        if let Some((params, defaults)) = declaration {
+            let (mut args, end) = type_args_at(&tokens, i + 1);
+            if args.len() < params.len() {
+                for default in defaults.iter().skip(args.len()) {
+                    let Some(default) = default else { break };
+                    let arg = default
+                        .iter()
+                        .flat_map(|t| match t {
+                            Token::Id(id) => match params.iter().position(|p| p == id).filter(|k| *k < args.len()) {
+                                Some(k) => args[k].clone(),
+                                None => vec![t.clone()],
+                            },
+                            t => vec![t.clone()],
+                        })
+                        .collect();
+                    args.push(arg);
+                }
+                let mut filled = vec![Token::Type(Type::Punct(Punct::LAngleB))];
+                for (k, arg) in args.into_iter().enumerate() {
+                    if k > 0 {
+                        filled.push(Token::Type(Type::Punct(Punct::Comma)));
+                    }
+                    filled.extend(arg);
+                }
+                filled.push(Token::Type(Type::Punct(Punct::RAngleB)));
+                offsets.splice(i + 1..end, vec![offsets[i]; filled.len()]);
+                tokens.splice(i + 1..end, filled);
+            }
+        }
+        i += 1;
+    }
+    (tokens, offsets)
+}
+
+/// Whether the token at `i` starts a declaration, so the name after it isn't a use.
+fn declares(tokens: &[Token], i: usize) -> bool {
+    matches!(tokens.get(i), Some(Token::Interface)) || tokens.get(i) == Some(&Token::Id("type".to_string()))
+}
+
+pub fn parse_arrays(value: &mut Vec<Value>) {
+    if value.is_empty() {
+        return;
+    }
+    let mut i = value.len() - 1;
+    while i > 0 {
+        if let (&Value::Type(Type::Punct(Punct::LBrack)), &Value::Type(Type::Punct(Punct::RBrack))) =
+            (&value[i - 1], &value[i])
+        {
+            let end = i;
+            let start: usize;
+            let mut j = i - 2;
+            let mut par_count = 0;
+            let mut args: Vec<Value> = Vec::new();
+            loop {
+                match &value[j] {
+                    Value::Type(Type::Punct(Punct::RBrack)) => {
+                        args.push(Value::Type(Type::Punct(Punct::RBrack)));
+                        j -= 1;
+                        continue;
+                    }
+                    Value::Type(Type::Punct(Punct::LBrack)) => {
+                        args.push(Value::Type(Type::Punct(Punct::LBrack)));
+                        j -= 1;
+                        continue;
+                    }
+                    Value::Type(Type::Punct(Punct::RPar)) => par_count += 1,
+                    Value::Type(Type::Punct(Punct::LPar)) => {
+                        par_count -= 1;
+                        if par_count == 0 && !args.is_empty() {
+                            start = j;
+                            args.reverse();
+                            value.splice(
+                                start..=end,
+                                [Value::Entry(Entry {
+                                    key: Key::Generic(Generic::Array),
+                                    value: args,
+                                    doc: None,
+                                    params: Vec::new(),
+                                    span: Span::default(),
+                                })],
+                            );
+                            if let Value::Entry(e) = &mut value[start] {
+                                parse_arrays(&mut e.value);
+                            }
+                            if start == 0 {
+                                i = start;
+                            } else {
+                                i = start - 1;
+                            }
+                            break;
+                        } else if par_count == 0 && args.is_empty() {
+                            panic!("unexpected Value before []");
+                        }
+                    }
+                    t => {
+                        args.push(t.clone());
+                        if par_count == 0 && !args.is_empty() {
+                            start = j;
+                            args.reverse();
+                            value.splice(
+                                start..=end,
+                                [Value::Entry(Entry {
+                                    key: Key::Generic(Generic::Array),
+                                    value: args,
+                                    doc: None,
+                                    params: Vec::new(),
+                                    span: Span::default(),
+                                })],
+                            );
+                            if let Value::Entry(e) = &mut value[start] {
+                                parse_arrays(&mut e.value);
+                            }
+                            if start == 0 {
+                                i = start;
+                            } else {
+                                i = start - 1;
+                            }
+                            break;
+                        } else if par_count == 0 && args.is_empty() {
+                            panic!("unexpected Value before []");
+                        }
+                    }
+                }
+                j -= 1;
+            }
+        } else {
+            i -= 1;
+        }
+    }
+}
+
+pub fn parse_generics(value: &mut Vec<Value>) {
+    let mut i = 1;
+    while i < value.len() {
+        if let &Value::Type(Type::Punct(Punct::LAngleB)) = &value[i] {
+            let generic_name: Generic = match &value[i - 1] {
+                Value::Type(Type::Custom(str)) => match str.clone().as_str() {
+                    "Array" => Generic::Array,
+                    _ => Generic::Custom(str.clone()),
+                },
+                _ => panic!("Unexpected generic name"),
+            };
+            let start = i - 1;
+            let end: usize;
+            let mut j = i + 1;
+            let mut args: Vec<Value> = Vec::new();
+            let mut count = 1;
+            while j < value.len() {
+                match &value[j] {
+                    Value::Type(Type::Punct(Punct::LAngleB)) => {
+                        args.push(Value::Type(Type::Punct(Punct::LAngleB)));
+                        count += 1;
+                    }
+                    Value::Type(Type::Punct(Punct::RAngleB)) => {
+                        count -= 1;
+                        if count == 0 && !args.is_empty() {
+                            end = j;
+                            value.splice(
+                                start..=end,
+                                [Value::Entry(Entry {
+                                    key: Key::Generic(generic_name),
+                                    value: args.clone(),
+                                    doc: None,
+                                    params: Vec::new(),
+                                    span: Span::default(),
+                                })],
+                            );
+                            if let Value::Entry(e) = &mut value[start] {
+                                parse_generics(&mut e.value);
+                            }
+                            break;
+                        } else if count == 0 && args.is_empty() {
+                            panic!("why?");
+                        }
+                        args.push(Value::Type(Type::Punct(Punct::RAngleB)));
+                    }
+                    t => {
+                        args.push(t.clone());
+                    }
+                }
+                j += 1;
+            }
+        }
+        i += 1;
+    }
+}
+
+pub fn for_each_value(entry: &mut Entry, f: fn(value: &mut Vec<Value>)) {
+    (f)(&mut entry.value);
+    for j in &mut entry.value {
+        if let Value::Entry(e) = j {
+            for_each_value(e, f);
+        }
+    }
+}
+
+/// The values with every span cleared, to compare types declared in different places.
+pub fn strip_spans(values: &[Value]) -> Vec<Value> {
+    values
+        .iter()
+        .map(|value| match value {
+            Value::Entry(e) => Value::Entry(Entry {
+                key: e.key.clone(),
+                value: strip_spans(&e.value),
+                doc: e.doc.clone(),
+                params: e.params.clone(),
+                span: Span::default(),
+            }),
+            value => value.clone(),
+        })
+        .collect()
+}
+
+pub fn parse_parens(entry: &mut Entry) {
+    let mut i = 0;
+    while i < entry.value.len() {
+        match &mut entry.value[i] {
+            Value::Entry(e) => {
+                parse_parens(e);
+            }
+            Value::Type(t) => {
+                if let Type::Punct(Punct::LPar) = t {
+                    let start = i;
+                    let end: usize;
+                    let mut j = i + 1;
+                    let mut par_count = 1;
+                    let mut value: Vec<Value> = Vec::new();
+                    while j < entry.value.len() {
+                        match &mut entry.value[j] {
+                            Value::Type(Type::Punct(Punct::LPar)) => {
+                                par_count += 1;
+                                value.push(Value::Type(Type::Punct(Punct::LPar)));
+                            }
+                            Value::Type(Type::Punct(Punct::RPar)) => {
+                                par_count -= 1;
+                                if par_count == 0 {
+                                    end = j;
+                                    entry.value.splice(
+                                        start..=end,
+                                        [Value::Entry(Entry {
+                                            key: Key::Paren,
+                                            value,
+                                            doc: None,
+                                            params: Vec::new(),
+                                            span: Span::default(),
+                                        })],
+                                    );
+                                    if let Value::Entry(e) = &mut entry.value[i] {
+                                        parse_parens(e);
+                                    }
+                                    break;
+                                } else {
+                                    value.push(Value::Type(Type::Punct(Punct::RPar)));
+                                }
+                            }
+                            t => {
+                                value.push(t.clone());
+                            }
+                        }
+                        j += 1;
+                    }
+                }
+            }
+        }
+        i += 1;
+    }
+}
+
+pub fn parse_tuples(entry: &mut Entry) {
+    let mut i = 0;
+    while i < entry.value.len() {
+        match &mut entry.value[i] {
+            Value::Entry(e) => {
+                parse_tuples(e);
+            }
+            Value::Type(t) => {
+                if let Type::Punct(Punct::LBrack) = t {
+                    if let Value::Type(Type::Punct(Punct::RBrack)) = entry.value[i + 1] {
+                        i += 1;
+                        continue;
+                    }
+                    let start = i;
+                    let end: usize;
+                    let mut j = i + 1;
+                    let mut brack_count = 1;
+                    let mut value: Vec<Value> = Vec::new();
+                    while j < entry.value.len() {
+                        match &mut entry.value[j] {
+                            Value::Type(Type::Punct(Punct::LBrack)) => {
+                                brack_count += 1;
+                                value.push(Value::Type(Type::Punct(Punct::LBrack)));
+                            }
+                            Value::Type(Type::Punct(Punct::RBrack)) => {
+                                brack_count -= 1;
+                                if brack_count == 0 {
+                                    end = j;
+                                    entry.value.splice(
+                                        start..=end,
+                                        [Value::Entry(Entry {
+                                            key: Key::Tuple,
+                                            value,
+                                            doc: None,
+                                            params: Vec::new(),
+                                            span: Span::default(),
+                                        })],
+                                    );
+                                    if let Value::Entry(e) = &mut entry.value[i] {
+                                        parse_tuples(e);
+                                    }
+                                    break;
+                                } else {
+                                    value.push(Value::Type(Type::Punct(Punct::RBrack)));
+                                }
+                            }
+                            t => {
+                                value.push(t.clone());
+                            }
+                        }
+                        j += 1;
+                    }
+                }
+            }
+        }
+        i += 1;
+    }
+}
+
+pub fn parse_and(entry: &mut Entry) {
+    let mut i = 0;
+    while i < entry.value.len() {
+        match &mut entry.value[i] {
+            Value::Entry(e) => {
+                parse_and(e);
+                i += 1;
+            }
+            Value::Type(Type::Oper(Oper::And)) => {
+                if i == 0 || i == entry.value.len() - 1 {
+                    entry.value.remove(i);
+                } else {
+                    entry.value.splice(
+                        (i - 1)..=(i + 1),
+                        [Value::Entry(Entry {
+                            key: Key::And,
+                            value: vec![entry.value[i - 1].clone(), entry.value[i + 1].clone()],
+                            doc: None,
+                            params: Vec::new(),
+                            span: Span::default(),
+                        })],
+                    );
+                    i -= 1;
+                }
+            }
+            _ => i += 1,
+        }
+    }
+}
+
+pub fn parse_or(entry: &mut Entry) {
+    let mut i = 0;
+    while i < entry.value.len() {
+        match &mut entry.value[i] {
+            Value::Entry(e) => {
+                parse_or(e);
+                i += 1;
+            }
+            Value::Type(Type::Oper(Oper::Or)) => {
+                if i == 0 || i == entry.value.len() - 1 {
+                    entry.value.remove(i);
+                } else {
+                    entry.value.splice(
+                        (i - 1)..=(i + 1),
+                        [Value::Entry(Entry {
+                            key: Key::Or,
+                            value: vec![entry.value[i - 1].clone(), entry.value[i + 1].clone()],
+                            doc: None,
+                            params: Vec::new(),
+                            span: Span::default(),
+                        })],
+                    );
+                    i -= 1;
+                }
+            }
+            _ => i += 1,
+        }
+    }
+}
+
+pub fn parse_custom_types(entry: &mut Entry, interfaces: &[Entry]) {
+    let expanding = match &entry.key {
+        Key::Name(n) | Key::Alias(n) => vec![n.clone()],
+        _ => Vec::new(),
+    };
+    inline_custom_types(&mut entry.value, interfaces, &expanding);
+}
+
+/// `expanding` holds the declarations being inlined around `values`, so recursive references stop there.
+fn inline_custom_types(values: &mut [Value], interfaces: &[Entry], expanding: &[String]) {
+    for current in values.iter_mut() {
+        match current {
+            Value::Entry(Entry {
+                key: Key::Generic(Generic::Custom(name)),
+                value: args,
+                ..
+            }) => {
+                let name = name.clone();
+                if let Some(j) = find_interface(interfaces, &name).filter(|j| !j.params.is_empty()) {
+                    let args = split_type_args(args);
+                    if args.len() != j.params.len() {
+                        panic!(
+                            "{} expects {} type arguments, found {}",
+                            name,
+                            j.params.len(),
+                            args.len()
+                        );
+                    }
+                    if expanding.contains(&name) {
+                        if let Key::Alias(_) = j.key {
+                            panic!("Recursive type alias {} is not supported, use an interface", name);
+                        }
+                        let mut value: Vec<Value> = args.into_iter().map(paren).collect();
+                        inline_custom_types(&mut value, interfaces, expanding);
+                        *current = Value::Entry(Entry {
+                            key: Key::Generic(Generic::Ref(name)),
+                            value,
+                            doc: None,
+                            params: Vec::new(),
+                            span: Span::default(),
+                        });
+                        continue;
+                    }
+                    let mut value = j.value.clone();
+                    substitute_params(&mut value, &j.params, &args);
+                    *current = inline(j, value);
+                    if let Value::Entry(e) = current {
+                        inline_custom_types(&mut e.value, interfaces, &[expanding, &[name]].concat());
+                    }
+                } else if let Value::Entry(e) = current {
+                    inline_custom_types(&mut e.value, interfaces, expanding);
+                }
+            }
+            Value::Entry(e) => inline_custom_types(&mut e.value, interfaces, expanding),
+            Value::Type(Type::Custom(custom_type)) => {
+                let name = custom_type.clone();
+                if let Some(j) = find_interface(interfaces, &name) {
+                    if !j.params.is_empty() {
+                        panic!("Generic interface {} used without type arguments", name);
+                    }
+                    let value = if expanding.contains(&name) {
+                        if let Key::Alias(_) = j.key {
+                            panic!("Recursive type alias {} is not supported, use an interface", name);
+                        }
+                        Vec::new()
+                    } else {
+                        let mut value = vec![inline(j, j.value.clone())];
+                        inline_custom_types(
+                            &mut value,
+                            interfaces,
+                            &[expanding, std::slice::from_ref(&name)].concat(),
+                        );
+                        value
+                    };
+                    *current = Value::Entry(Entry {
+                        key: Key::Ref(name),
+                        value,
+                        doc: None,
+                        params: Vec::new(),
+                        span: Span::default(),
+                    });
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+fn find_interface<'a>(interfaces: &'a [Entry], name: &str) -> Option<&'a Entry> {
+    interfaces
+        .iter()
+        .find(|i| matches!(&i.key, Key::Name(n) | Key::Alias(n) if n == name))
+}
+
+/// An interface becomes an object type, an alias its parenthesized right-hand side.
+fn inline(declaration: &Entry, value: Vec<Value>) -> Value {
+    Value::Entry(Entry {
+        key: match declaration.key {
+            Key::Alias(_) => Key::Paren,
+            _ => Key::None,
+        },
+        value,
+        doc: None,
+        params: Vec::new(),
+        span: declaration.span,
+    })
+}
+
+/// Splits the arguments of `Name<A, B>` on top-level commas.
+fn split_type_args(args: &[Value]) -> Vec<Vec<Value>> {
+    let mut split = vec![Vec::new()];
+    for arg in args {
+        match arg {
+            Value::Type(Type::Punct(Punct::Comma)) => split.push(Vec::new()),
+            arg => split.last_mut().unwrap().push(arg.clone()),
+        }
+    }
+    split
+}
+
+/// Replaces the type parameters of an instantiated generic interface with its arguments, each wrapped in
+/// parentheses so `T[]` with `T = A | B` stays `(A | B)[]`.
+fn substitute_params(value: &mut [Value], params: &[String], args: &[Vec<Value>]) {
+    for i in value.iter_mut() {
+        match i {
+            Value::Entry(e) => substitute_params(&mut e.value, params, args),
+            Value::Type(Type::Param(p)) => {
+                if let Some(k) = params.iter().position(|i| i == p) {
+                    *i = paren(args[k].clone());
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+fn paren(value: Vec<Value>) -> Value {
+    Value::Entry(Entry {
+        key: Key::Paren,
+        value,
+        doc: None,
+        params: Vec::new(),
+        span: Span::default(),
+    })
+}
+
+pub fn merge_interfaces(interfaces: &mut Vec<Entry>) {
+    let mut i = 0;
+    while i < interfaces.len() {
+        let current_name = if let Key::Name(str) = &interfaces[i].key {
+            str.clone()
+        } else {
+            unreachable!()
+        };
+        let mut j = i + 1;
+        while j < interfaces.len() {
+            if let Key::Name(str) = &interfaces[j].key {
+                if str.clone() == current_name {
+                    for k in 0..interfaces[j].value.len() {
+                        let clone = interfaces[j].clone().value[k].clone();
+                        interfaces[i].value.push(clone);
+                    }
+                    interfaces.remove(j);
+                    j -= 1;
+                }
+            } else {
+                unreachable!()
+            }
+            j += 1;
+        }
+        i += 1;
+    }
+}
+
+/// Replaces intersections of object types with one object holding the properties of all of them, since each inlined
+/// check asserts an exact key count and `A & B` could otherwise never pass. Properties whose types can't intersect
+/// are reported on stderr.
+pub fn merge_intersections(entry: &mut Entry) {
+    let interface = match &entry.key {
+        Key::Name(n) | Key::Alias(n) => n.clone(),
+        _ => String::new(),
+    };
+    merge_intersections_in(&mut entry.value, &interface);
+}
+
+fn merge_intersections_in(values: &mut [Value], interface: &str) {
+    for value in values.iter_mut() {
+        if let Value::Entry(e) = value {
+            merge_intersections_in(&mut e.value, interface);
+            if e.key != Key::And {
+                continue;
+            }
+            let members = and_members(e);
+            let objects: Vec<&Entry> = members
+                .iter()
+                .filter_map(|i| match i {
+                    Value::Entry(o) if o.key == Key::None => Some(o),
+                    _ => None,
+                })
+                .collect();
+            if objects.len() == members.len() {
+                *value = Value::Entry(merge_objects(objects, interface));
+            }
+        }
+    }
+}
+
+fn merge_objects(objects: Vec<&Entry>, interface: &str) -> Entry {
+    let mut properties: Vec<Entry> = Vec::new();
+    for property in objects.iter().flat_map(|i| &i.value) {
+        let Value::Entry(property) = property else {
+            continue;
+        };
+        let name = match &property.key {
+            Key::Name(n) | Key::Optional(n) => n,
+            _ => continue,
+        };
+        let existing = properties
+            .iter_mut()
+            .find(|i| matches!(&i.key, Key::Name(n) | Key::Optional(n) if n == name));
+        match existing {
+            None => properties.push(property.clone()),
+            Some(existing) => {
+                if matches!(property.key, Key::Name(_)) {
+                    existing.key = Key::Name(name.clone());
+                }
+                existing.value = merge_property_values(&existing.value, &property.value, name, interface);
+                existing.doc = match (existing.doc.take(), &property.doc) {
+                    (Some(mut doc), Some(other)) => {
+                        doc.tags.extend(other.tags.clone());
+                        Some(doc)
+                    }
+                    (doc, other) => doc.or(other.clone()),
+                };
+            }
+        }
+    }
+    Entry {
+        key: Key::None,
+        value: properties.into_iter().map(Value::Entry).collect(),
+        doc: None,
+        params: Vec::new(),
+        span: Span::default(),
+    }
+}
+
+fn merge_property_values(a: &[Value], b: &[Value], name: &str, interface: &str) -> Vec<Value> {
+    if strip_spans(a) == strip_spans(b) {
+        return a.to_vec();
+    }
+    let single = |values: &[Value]| match values {
+        [value] => Some(unparen(value).clone()),
+        _ => None,
+    };
+    if let (Some(Value::Entry(x)), Some(Value::Entry(y))) = (single(a), single(b)) {
+        if x.key == Key::None && y.key == Key::None {
+            return vec![Value::Entry(merge_objects(vec![&x, &y], interface))];
+        }
+    }
+    let (x, y) = (single(a), single(b));
+    let conflict = match (x.as_ref().and_then(leaf_kind), y.as_ref().and_then(leaf_kind)) {
+        (Some(k), Some(l)) => k != l || is_literal(x.as_ref().unwrap()) && is_literal(y.as_ref().unwrap()),
+        _ => false,
+    };
+    let to_value = |values: &[Value]| match values {
+        [value] => value.clone(),
+        values => Value::Entry(Entry {
+            key: Key::Paren,
+            value: values.to_vec(),
+            doc: None,
+            params: Vec::new(),
+            span: Span::default(),
+        }),
+    };
+    let and = Entry {
+        key: Key::And,
+        value: vec![to_value(a), to_value(b)],
+        doc: None,
+        params: Vec::new(),
+        span: Span::default(),
+    };
+    if conflict {
+        eprintln!(
+            "Warning: property \"{}\" of {} is both {} and {} in an intersection, it can never match",
+            name,
+            if interface.is_empty() { "a type" } else { interface },
+            value_to_ts_string(&to_value(a)),
+            value_to_ts_string(&to_value(b))
+        );
+    }
+    vec![Value::Entry(and)]
+}
+
+/// What `typeof` (or `=== null`) tells apart, for leaf types whose intersection can be known to be empty.
+fn leaf_kind(value: &Value) -> Option<&'static str> {
+    match value {
+        Value::Type(t) => match t {
+            Type::String | Type::StringLit(_) => Some("string"),
+            Type::Number | Type::NumberLit(_) => Some("number"),
+            Type::Boolean | Type::True | Type::False => Some("boolean"),
+            Type::BigInt => Some("bigint"),
+            Type::Symbol => Some("symbol"),
+            Type::Null => Some("null"),
+            Type::Undefined => Some("undefined"),
+            Type::Function => Some("function"),
+            _ => None,
+        },
+        Value::Entry(Entry { key: Key::None, .. }) => Some("object"),
+        _ => None,
+    }
+}
+
+fn is_literal(value: &Value) -> bool {
+    matches!(
+        value,
+        Value::Type(Type::StringLit(_) | Type::NumberLit(_) | Type::True | Type::False)
+    )
+}
+
+pub fn or_branches(entry: &Entry) -> Vec<Value> {
+    let mut branches: Vec<Value> = Vec::new();
+    for value in &entry.value {
+        match value {
+            Value::Entry(e) if e.key == Key::Or => branches.extend(or_branches(e)),
+            Value::Entry(Entry {
+                key: Key::Paren | Key::Ref(_),
+                ..
+            }) => {
+                if let Value::Entry(e @ Entry { key: Key::Or, .. }) = unparen(value) {
+                    branches.extend(or_branches(e));
+                } else {
+                    branches.push(value.clone());
+                }
+            }
+            _ => branches.push(value.clone()),
+        }
+    }
+    branches
+}
+
+pub fn value_to_ts_string(value: &Value) -> String {
+    match value {
+        Value::Type(t) => match t {
+            Type::Custom(name) | Type::Param(name) => name.clone(),
+            Type::StringLit(str) => format!("\"{}\"", str),
+            Type::NumberLit(str) => str.clone(),
+            Type::True => "true".to_string(),
+            Type::False => "false".to_string(),
+            Type::String => "string".to_string(),
+            Type::Number => "number".to_string(),
+            Type::Object => "object".to_string(),
+            Type::Boolean => "boolean".to_string(),
+            Type::Null => "null".to_string(),
+            Type::Undefined => "undefined".to_string(),
+            Type::Function => "Function".to_string(),
+            Type::Symbol => "symbol".to_string(),
+            Type::BigInt => "bigint".to_string(),
+            Type::Any => "any".to_string(),
+            Type::Unknown => "unknown".to_string(),
+            Type::Oper(_) | Type::Punct(_) => "".to_string(),
+        },
+        Value::Entry(e) => {
+            let values = || {
+                e.value
+                    .iter()
+                    .map(value_to_ts_string)
+                    .filter(|i| !i.is_empty())
+                    .collect::<Vec<String>>()
+            };
+            match &e.key {
+                Key::Ref(n) => n.clone(),
+                Key::Name(_) | Key::Optional(_) | Key::Paren | Key::Alias(_) => values().join(" "),
+                Key::Or => or_branches(e)
+                    .iter()
+                    .map(value_to_ts_string)
+                    .collect::<Vec<String>>()
+                    .join(" | "),
+                Key::And => values().join(" & "),
+                Key::Tuple => format!("[{}]", values().join(", ")),
+                Key::Generic(Generic::Array) => {
+                    let inner = values().join(" ");
+                    if let Some(Value::Entry(Entry {
+                        key: Key::Or | Key::And,
+                        ..
+                    })) = e.value.first()
+                    {
+                        format!("({})[]", inner)
+                    } else {
+                        format!("{}[]", inner)
+                    }
+                }
+                Key::Generic(Generic::Custom(name) | Generic::Ref(name)) => {
+                    format!("{}<{}>", name, values().join(", "))
+                }
+                Key::None => {
+                    let props = e
+                        .value
+                        .iter()
+                        .map(|i| match i {
+                            Value::Entry(Entry { key: Key::Name(n), .. }) => {
+                                format!("{}: {}", n, value_to_ts_string(i))
+                            }
+                            Value::Entry(Entry {
+                                key: Key::Optional(n), ..
+                            }) => format!("{}?: {}", n, value_to_ts_string(i)),
+                            _ => value_to_ts_string(i),
+                        })
+                        .collect::<Vec<String>>();
+                    if props.is_empty() {
+                        "{}".to_string()
+                    } else {
+                        format!("{{ {} }}", props.join("; "))
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// `string & { readonly __brand: "UserId" }`: a primitive intersected with object types that only exist at compile
+/// time. `name` is the first string literal property of the objects (`"UserId"`), or else the first property name.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Brand {
+    pub name: Option<String>,
+    pub base: Value,
+}
+
+pub fn brand(entry: &Entry) -> Option<Brand> {
+    let members = and_members(entry);
+    let (bases, objects): (Vec<&Value>, Vec<&Value>) = members.iter().partition(|i| {
+        matches!(
+            i,
+            Value::Type(Type::String | Type::Number | Type::BigInt | Type::Boolean | Type::Symbol)
+        )
+    });
+    if bases.len() != 1
+        || objects.is_empty()
+        || !objects
+            .iter()
+            .all(|i| matches!(i, Value::Entry(Entry { key: Key::None, .. })))
+    {
+        return None;
+    }
+    let properties: Vec<&Entry> = objects
+        .iter()
+        .filter_map(|i| match i {
+            Value::Entry(e) => Some(e),
+            _ => None,
+        })
+        .flat_map(|i| &i.value)
+        .filter_map(|i| match i {
+            Value::Entry(e) if matches!(e.key, Key::Name(_) | Key::Optional(_)) => Some(e),
+            _ => None,
+        })
+        .collect();
+    let literal = properties.iter().find_map(|i| match i.value.as_slice() {
+        [value] => match unparen(value) {
+            Value::Type(Type::StringLit(str)) => Some(str.clone()),
+            _ => None,
+        },
+        _ => None,
+    });
+    let name = literal.or_else(|| {
+        properties.first().map(|i| match &i.key {
+            Key::Name(n) | Key::Optional(n) => n.clone(),
+            _ => unreachable!(),
+        })
+    });
+    Some(Brand {
+        name,
+        base: bases[0].clone(),
+    })
+}
+
+fn and_members(entry: &Entry) -> Vec<Value> {
+    let mut members: Vec<Value> = Vec::new();
+    for value in &entry.value {
+        match unparen(value) {
+            Value::Entry(e) if e.key == Key::And => members.extend(and_members(e)),
+            value => members.push(value.clone()),
+        }
+    }
+    members
+}
+
+/// The type inside single-element parentheses and references.
+pub fn unparen(value: &Value) -> &Value {
+    match value {
+        Value::Entry(Entry {
+            key: Key::Paren | Key::Ref(_),
+            value,
+            ..
+        }) if value.len() == 1 => unparen(&value[0]),
+        value => value,
+    }
+}
+
+/// A union whose object members all carry a literal-typed property (the discriminant) with disjoint values.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Discriminated {
+    pub key: String,
+    pub cases: Vec<(Vec<Type>, Value)>,
+    pub others: Vec<Value>,
+}
+
+pub fn discriminate(branches: Vec<Value>) -> Option<Discriminated> {
+    let (objects, others): (Vec<Value>, Vec<Value>) = branches
+        .into_iter()
+        .partition(|i| matches!(unparen(i), Value::Entry(Entry { key: Key::None, .. })));
+    if objects.len() < 2 {
+        return None;
+    }
+    let props = |object: &Value| -> Vec<(String, Vec<Type>)> {
+        let Value::Entry(e) = unparen(object) else {
+            return Vec::new();
+        };
+        e.value
+            .iter()
+            .filter_map(|i| match i {
+                Value::Entry(Entry {
+                    key: Key::Name(n),
+                    value,
+                    ..
+                }) if value.len() == 1 => literals(&value[0]).map(|l| (n.clone(), l)),
+                _ => None,
+            })
+            .collect()
+    };
+    let candidates = props(&objects[0]);
+    for (key, _) in candidates {
+        let cases: Option<Vec<(Vec<Type>, Value)>> = objects
+            .iter()
+            .map(|o| {
+                props(o)
+                    .into_iter()
+                    .find(|(n, _)| *n == key)
+                    .map(|(_, l)| (l, o.clone()))
+            })
+            .collect();
+        let Some(cases) = cases else { continue };
+        let labels: Vec<&Type> = cases.iter().flat_map(|(l, _)| l).collect();
+        if labels.iter().enumerate().all(|(i, l)| !labels[i + 1..].contains(l)) {
+            return Some(Discriminated { key, cases, others });
+        }
+    }
+    None
+}
+
+pub fn literals(value: &Value) -> Option<Vec<Type>> {
+    match unparen(value) {
+        Value::Type(t @ (Type::StringLit(_) | Type::NumberLit(_) | Type::True | Type::False | Type::Null)) => {
+            Some(vec![t.clone()])
+        }
+        Value::Entry(e) if e.key == Key::Or => {
+            let mut all = Vec::new();
+            for i in or_branches(e) {
+                all.extend(literals(&i)?);
+            }
+            Some(all)
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,826 @@
+use crate::cmd::Style;
+use crate::source_map::{parse_marker, Mark};
+
+const PUNCTUATORS: [&str; 38] = [
+    "===", "!==", "...", "=>", "==", "!=", "<=", ">=", "&&", "||", "??", "++", "--", "+=", "-=", "{", "}", "(", ")",
+    "[", "]", ";", ",", ".", ":", "?", "!", "=", "<", ">", "+", "-", "*", "/", "%", "|", "&", "~",
+];
+
+/// Operators printed with a space on both sides.
+const BINARY: [&str; 22] = [
+    "===", "!==", "==", "!=", "<=", ">=", "<", ">", "&&", "||", "??", "+=", "-=", "=>", "=", "+", "-", "*", "/", "%",
+    "|", "&",
+];
+
+/// Keywords followed by a space before a parenthesis, bracket or string.
+const KEYWORDS: [&str; 13] = [
+    "if", "for", "while", "switch", "catch", "return", "typeof", "function", "in", "of", "else", "case", "throw",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// Identifiers, keywords and numbers, with any type arguments attached.
+    Word(String),
+    Str(String),
+    Regex(String),
+    Punct(&'static str),
+}
+
+struct Lexed {
+    token: Token,
+    /// Whether whitespace came before the token.
+    space: bool,
+    /// Whether an empty line came right before the token.
+    blank: bool,
+    /// The `/* */` comments before the token.
+    comments: Vec<Comment>,
+}
+
+struct Comment {
+    text: String,
+    /// Whether an empty line came before the comment.
+    blank: bool,
+    /// Index of the first character of the comment in the code.
+    start: usize,
+}
+
+enum Doc {
+    Text(String),
+    /// A source map marker, which takes no room: the printed position is recorded with its offset in the input.
+    Mark(usize),
+    /// The text when the enclosing group fits on the line, a line break otherwise.
+    Line(&'static str),
+    /// The text when the enclosing group doesn't fit on the line, nothing otherwise.
+    IfBreak(&'static str),
+    Hard,
+    Concat(Vec<Doc>),
+    Indent(Box<Doc>),
+    Group(Box<Doc>),
+}
+
+/// Lays out the generated JavaScript or TypeScript, which is minified, the way prettier would: statements on lines of
+/// their own, and groups that don't fit in `style.print_width` broken at commas, `&&`, `||` and `?:`.
+pub fn pretty_print(js: &str, style: &Style) -> String {
+    pretty_print_marked(js, style).0
+}
+
+/// `pretty_print`, also returning where the source map markers of `js` ended up. They are left out of the code.
+pub fn pretty_print_marked(js: &str, style: &Style) -> (String, Vec<Mark>) {
+    let tokens = tokenize(js);
+    let mut close = vec![0; tokens.len()];
+    let mut block = vec![false; tokens.len()];
+    let mut open: Vec<usize> = Vec::new();
+    // Colons that end a `case` or `default` label, and the depth of the `case` whose colon is still to come.
+    let mut label = vec![false; tokens.len()];
+    let mut case: Option<usize> = None;
+    // Depth of the `function` or `namespace` whose body is the next block at that depth.
+    let mut body: Option<usize> = None;
+    for (i, t) in tokens.iter().enumerate() {
+        match &t.token {
+            Token::Punct("(" | "[") => open.push(i),
+            Token::Punct("{") => {
+                block[i] = body == Some(open.len())
+                    || match i.checked_sub(1).map(|p| (p, &tokens[p].token)) {
+                        None => true,
+                        Some((_, Token::Punct(")" | ";" | "=>"))) => true,
+                        Some((p, Token::Punct(":"))) => label[p],
+                        Some((p, Token::Punct("{"))) => block[p],
+                        Some((p, Token::Punct("}"))) => block[close[p]],
+                        Some((_, Token::Word(w))) => matches!(w.as_str(), "else" | "try" | "finally"),
+                        _ => false,
+                    };
+                if block[i] && body == Some(open.len()) {
+                    body = None;
+                }
+                open.push(i);
+            }
+            Token::Punct(")" | "]" | "}") => {
+                let o = open.pop().expect("Unbalanced brackets in the generated code");
+                close[o] = i;
+                // Lets a closing brace find whether it ends a block.
+                close[i] = o;
+            }
+            Token::Punct(";") if body == Some(open.len()) => body = None,
+            Token::Punct(":") if case == Some(open.len()) => {
+                label[i] = true;
+                case = None;
+            }
+            Token::Word(w) if w == "case" => case = Some(open.len()),
+            Token::Word(w) if w == "default" && tokens.get(i + 1).is_some_and(|t| t.token == Token::Punct(":")) => {
+                label[i + 1] = true
+            }
+            Token::Word(w) if w == "function" || w == "namespace" => body = Some(open.len()),
+            Token::Word(w) if w == "global" && i > 0 && tokens[i - 1].token == Token::Word("declare".to_string()) => {
+                body = Some(open.len())
+            }
+            _ => {}
+        }
+    }
+    let printer = Printer {
+        tokens: &tokens,
+        close,
+        block,
+        style,
+    };
+    let doc = Doc::Concat(printer.statements(0, tokens.len()));
+    let mut marks = Vec::new();
+    let mut string = layout(&doc, style, &mut marks);
+    string.push('\n');
+    (string, marks)
+}
+
+/// Takes the source map markers out of `js` as it is, returning the code and where the markers were.
+pub fn strip_markers(js: &str) -> (String, Vec<Mark>) {
+    let chars: Vec<char> = js.chars().collect();
+    let mut string = String::with_capacity(js.len());
+    let mut marks = Vec::new();
+    let (mut i, mut line) = (0, 0);
+    for comment in tokenize(js).iter().flat_map(|t| &t.comments) {
+        let Some(offset) = parse_marker(&comment.text) else {
+            continue;
+        };
+        string.extend(&chars[i..comment.start]);
+        line += chars[i..comment.start].iter().filter(|c| **c == '\n').count();
+        marks.push(mark(&string, line, offset));
+        i = comment.start + comment.text.chars().count();
+    }
+    string.extend(&chars[i..]);
+    (string, marks)
+}
+
+/// The position at the end of `string`, which is on `line`, with the column in UTF-16 code units as source maps count
+/// them.
+fn mark(string: &str, line: usize, offset: usize) -> Mark {
+    let line_start = string.rfind('\n').map_or(0, |i| i + 1);
+    Mark {
+        line,
+        column: string[line_start..].encode_utf16().count(),
+        offset,
+    }
+}
+
+fn tokenize(js: &str) -> Vec<Lexed> {
+    let chars: Vec<char> = js.chars().collect();
+    let mut tokens: Vec<Lexed> = Vec::new();
+    let mut i = 0;
+    loop {
+        let start = i;
+        let mut comments = Vec::new();
+        let mut newlines = 0;
+        loop {
+            while i < chars.len() && chars[i].is_whitespace() {
+                newlines += usize::from(chars[i] == '\n');
+                i += 1;
+            }
+            if chars[i..].starts_with(&['/', '*']) {
+                let from = i;
+                i = chars[i + 2..]
+                    .windows(2)
+                    .position(|w| w == ['*', '/'])
+                    .map_or(chars.len(), |end| i + end + 4);
+                comments.push(Comment {
+                    text: chars[from..i].iter().collect(),
+                    blank: newlines > 1,
+                    start: from,
+                });
+                newlines = 0;
+            } else {
+                break;
+            }
+        }
+        if i == chars.len() {
+            return tokens;
+        }
+        let space = chars[start..i].iter().any(|c| c.is_whitespace());
+        let blank = newlines > 1;
+        let c = chars[i];
+        let from = i;
+        let token = if c == '"' || c == '\'' {
+            i += 1;
+            while i < chars.len() && chars[i] != c {
+                i += if chars[i] == '\\' { 2 } else { 1 };
+            }
+            i += 1;
+            Token::Str(chars[from..i.min(chars.len())].iter().collect())
+        } else if c.is_alphanumeric() || c == '_' || c == '$' {
+            let number = c.is_ascii_digit();
+            while i < chars.len()
+                && (chars[i].is_alphanumeric() || chars[i] == '_' || chars[i] == '$' || number && chars[i] == '.')
+            {
+                i += 1;
+            }
+            let mut word: String = chars[from..i].iter().collect();
+            if let Some(end) = type_arguments(&chars, i) {
+                let args: String = chars[i..end].iter().filter(|c| !c.is_whitespace()).collect();
+                word.push_str(&args.replace(',', ", "));
+                i = end;
+            }
+            Token::Word(word)
+        } else if c == '/' && regex_allowed(tokens.last().map(|t| &t.token)) {
+            let mut class = false;
+            i += 1;
+            while i < chars.len() && (chars[i] != '/' || class) {
+                match chars[i] {
+                    '\\' => i += 1,
+                    '[' => class = true,
+                    ']' => class = false,
+                    _ => {}
+                }
+                i += 1;
+            }
+            i += 1;
+            while i < chars.len() && chars[i].is_ascii_alphabetic() {
+                i += 1;
+            }
+            Token::Regex(chars[from..i.min(chars.len())].iter().collect())
+        } else {
+            let rest: String = chars[i..chars.len().min(i + 3)].iter().collect();
+            let punct = PUNCTUATORS
+                .iter()
+                .find(|p| rest.starts_with(**p))
+                .unwrap_or_else(|| panic!("Unexpected character in the generated code: {}", c));
+            i += punct.len();
+            Token::Punct(punct)
+        };
+        tokens.push(Lexed {
+            token,
+            space,
+            blank,
+            comments,
+        });
+    }
+}
+
+/// The end of the type arguments starting at `start`, if the `<` there opens some rather than comparing.
+fn type_arguments(chars: &[char], start: usize) -> Option<usize> {
+    if chars.get(start) != Some(&'<') {
+        return None;
+    }
+    let mut depth = 0;
+    for (i, c) in chars.iter().enumerate().skip(start) {
+        match c {
+            '<' => depth += 1,
+            '>' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i + 1);
+                }
+            }
+            c if c.is_alphanumeric() || matches!(c, '_' | '$' | ',' | ' ' | '[' | ']') => {}
+            _ => return None,
+        }
+    }
+    None
+}
+
+/// Whether a `/` after `previous` starts a regular expression rather than dividing.
+fn regex_allowed(previous: Option<&Token>) -> bool {
+    match previous {
+        None => true,
+        Some(Token::Punct(p)) => !matches!(*p, ")" | "]" | "}"),
+        Some(Token::Word(w)) => KEYWORDS.contains(&w.as_str()),
+        Some(_) => false,
+    }
+}
+
+struct Printer<'a> {
+    tokens: &'a [Lexed],
+    /// Index of the matching bracket, for every bracket.
+    close: Vec<usize>,
+    /// Whether a `{` opens a block rather than an object.
+    block: Vec<bool>,
+    style: &'a Style,
+}
+
+impl Printer<'_> {
+    fn token(&self, i: usize) -> &Token {
+        &self.tokens[i].token
+    }
+
+    fn is(&self, i: usize, end: usize, punct: &str) -> bool {
+        i < end && matches!(self.token(i), Token::Punct(p) if *p == punct)
+    }
+
+    fn is_word(&self, i: usize, end: usize, words: &[&str]) -> bool {
+        i < end && matches!(self.token(i), Token::Word(w) if words.contains(&w.as_str()))
+    }
+
+    /// The statements from `i` to `end`, one per line. Statements after a `case` label are indented under it.
+    fn statements(&self, mut i: usize, end: usize) -> Vec<Doc> {
+        let mut docs = Vec::new();
+        let mut in_case = false;
+        while i < end {
+            if self.is(i, end, ";") {
+                i += 1;
+                continue;
+            }
+            // Doc comments go on lines of their own before the statement.
+            let mut lines = Vec::new();
+            for comment in self.tokens[i].comments.iter().filter(|c| c.text.starts_with("/**")) {
+                if comment.blank && !(docs.is_empty() && lines.is_empty()) {
+                    lines.push(Doc::Hard);
+                }
+                for (n, line) in comment.text.lines().enumerate() {
+                    let line = line.trim();
+                    lines.push(text(&if n == 0 { line.to_string() } else { format!(" {line}") }));
+                    lines.push(Doc::Hard);
+                }
+            }
+            let blank = self.tokens[i].blank && !(docs.is_empty() && lines.is_empty());
+            let label = self.is_word(i, end, &["case", "default"]);
+            let (doc, next) = if label {
+                let colon = self.find(i, end, &[":"]).unwrap_or(end);
+                (Doc::Concat(vec![self.units(i, colon), text(":")]), colon + 1)
+            } else {
+                self.statement(i, end)
+            };
+            // The line break goes inside the indent so that the statement starts indented too.
+            if !docs.is_empty() {
+                lines.insert(0, Doc::Hard);
+            }
+            if blank {
+                lines.push(Doc::Hard);
+            }
+            lines.push(doc);
+            let doc = Doc::Concat(lines);
+            docs.push(if in_case && !label {
+                Doc::Indent(Box::new(doc))
+            } else {
+                doc
+            });
+            in_case |= label;
+            i = next;
+        }
+        docs
+    }
+
+    /// The statement starting at `i`, and the index after it.
+    fn statement(&self, i: usize, end: usize) -> (Doc, usize) {
+        if self.is_word(i, end, &["if", "for", "while"]) && self.is(i + 1, end, "(") {
+            let keyword = match self.token(i) {
+                Token::Word(w) => w.clone(),
+                _ => unreachable!(),
+            };
+            let mut docs = vec![self.comments(i), text(&format!("{keyword} ")), self.brackets(i + 1)];
+            let (body, mut next, is_block) = self.body(self.close[i + 1] + 1, end);
+            docs.push(body);
+            if keyword == "if" && self.is_word(next, end, &["else"]) {
+                docs.push(if is_block {
+                    text(" else")
+                } else {
+                    Doc::Concat(vec![Doc::Hard, text("else")])
+                });
+                let (body, after, _) = if self.is_word(next + 1, end, &["if", "switch"]) {
+                    let (statement, after) = self.statement(next + 1, end);
+                    (Doc::Concat(vec![text(" "), statement]), after, true)
+                } else {
+                    self.body(next + 1, end)
+                };
+                docs.push(body);
+                next = after;
+            }
+            return (Doc::Concat(docs), next);
+        }
+        if self.is(i, end, "{") && self.block[i] {
+            return (self.block(i), self.close[i] + 1);
+        }
+        let mut j = i;
+        let (stop, next, semi) = loop {
+            if j >= end || self.is(j, end, "}") {
+                break (j, j, true);
+            }
+            match self.token(j) {
+                Token::Punct(";") => break (j, j + 1, true),
+                Token::Punct("{") if self.block[j] => {
+                    j = self.close[j] + 1;
+                    if !self.is_word(j, end, &["catch", "finally"]) {
+                        break (j, j, false);
+                    }
+                }
+                Token::Punct("(" | "[" | "{") => j = self.close[j] + 1,
+                _ => j += 1,
+            }
+        };
+        let mut docs = Vec::new();
+        if !self.style.semi && matches!(self.token(i), Token::Punct("(" | "[") | Token::Regex(_)) {
+            docs.push(text(";"));
+        }
+        docs.push(self.expression(i, stop, true));
+        if semi && self.style.semi {
+            docs.push(text(";"));
+        }
+        (Doc::Concat(docs), next)
+    }
+
+    /// The body of an `if`, `else` or loop: a block on the same line, or a statement indented on the next line if it
+    /// doesn't fit.
+    fn body(&self, i: usize, end: usize) -> (Doc, usize, bool) {
+        if self.is(i, end, "{") && self.block[i] {
+            return (Doc::Concat(vec![text(" "), self.block(i)]), self.close[i] + 1, true);
+        }
+        let (statement, next) = self.statement(i, end);
+        let doc = Doc::Group(Box::new(Doc::Indent(Box::new(Doc::Concat(vec![
+            Doc::Line(" "),
+            statement,
+        ])))));
+        (doc, next, false)
+    }
+
+    fn block(&self, i: usize) -> Doc {
+        let statements = self.statements(i + 1, self.close[i]);
+        if statements.is_empty() {
+            return text("{}");
+        }
+        Doc::Concat(vec![
+            text("{"),
+            Doc::Indent(Box::new(Doc::Concat(
+                std::iter::once(Doc::Hard).chain(statements).collect(),
+            ))),
+            Doc::Hard,
+            text("}"),
+        ])
+    }
+
+    /// The first of `puncts` from `i` to `end` outside of brackets.
+    fn find(&self, i: usize, end: usize, puncts: &[&str]) -> Option<usize> {
+        self.split(i, end, puncts).get(1).map(|(start, _)| start - 1)
+    }
+
+    /// The ranges between the `puncts` from `i` to `end` outside of brackets.
+    fn split(&self, i: usize, end: usize, puncts: &[&str]) -> Vec<(usize, usize)> {
+        let mut ranges = Vec::new();
+        let mut start = i;
+        let mut j = i;
+        while j < end {
+            match self.token(j) {
+                Token::Punct("(" | "[" | "{") => j = self.close[j],
+                Token::Punct(p) if puncts.contains(p) => {
+                    ranges.push((start, j));
+                    start = j + 1;
+                }
+                _ => {}
+            }
+            j += 1;
+        }
+        ranges.push((start, end));
+        ranges
+    }
+
+    /// The expression from `i` to `end`, broken at its operators of lowest precedence. The operands after the first
+    /// are indented if `indent` is set.
+    fn expression(&self, i: usize, end: usize, indent: bool) -> Doc {
+        if self.is_word(i, end, &["return", "throw"]) && i + 1 < end {
+            return Doc::Concat(vec![
+                self.units(i, i + 1),
+                text(" "),
+                self.expression(i + 1, end, indent),
+            ]);
+        }
+        if let Some(eq) = self.find(i, end, &["=", "+=", "-="]) {
+            return Doc::Concat(vec![
+                self.expression(i, eq, indent),
+                text(&format!(" {} ", self.render(eq))),
+                self.expression(eq + 1, end, indent),
+            ]);
+        }
+        if let Some(arrow) = self.find(i, end, &["=>"]) {
+            return Doc::Concat(vec![
+                self.units(i, arrow),
+                text(" => "),
+                self.expression(arrow + 1, end, indent),
+            ]);
+        }
+        if let Some(question) = self.find(i, end, &["?"]) {
+            let mut nested = 0;
+            let colon = self.split(question + 1, end, &["?", ":"]).iter().find_map(|(_, e)| {
+                match self.is(*e, end, "?") {
+                    true => nested += 1,
+                    false if nested == 0 => return Some(*e),
+                    false => nested -= 1,
+                }
+                None
+            });
+            if let Some(colon) = colon.filter(|c| *c < end) {
+                return Doc::Group(Box::new(Doc::Concat(vec![
+                    self.expression(i, question, true),
+                    Doc::Indent(Box::new(Doc::Concat(vec![
+                        Doc::Line(" "),
+                        text("? "),
+                        self.expression(question + 1, colon, true),
+                        Doc::Line(" "),
+                        text(": "),
+                        self.expression(colon + 1, end, true),
+                    ]))),
+                ])));
+            }
+        }
+        for op in ["||", "&&"] {
+            let operands = self.split(i, end, &[op]);
+            if operands.len() > 1 {
+                let operands: Vec<Doc> = operands
+                    .iter()
+                    .map(|(start, stop)| {
+                        if op == "||" && self.find(*start, *stop, &["&&"]).is_some() {
+                            Doc::Concat(vec![text("("), self.expression(*start, *stop, true), text(")")])
+                        } else {
+                            self.expression(*start, *stop, true)
+                        }
+                    })
+                    .collect();
+                return chain(operands, op, indent);
+            }
+        }
+        let comparison = self
+            .find(i, end, &["===", "!==", "==", "!=", "<=", ">=", "<", ">"])
+            .is_some();
+        let terms = self.split(i, end, &["+"]);
+        if terms.len() > 1 && !comparison && terms.iter().all(|(start, stop)| start < stop) {
+            let terms = terms.iter().map(|(start, stop)| self.units(*start, *stop)).collect();
+            return chain(terms, "+", indent);
+        }
+        self.units(i, end)
+    }
+
+    /// The tokens from `i` to `end`, which have no operator to break at outside of brackets.
+    fn units(&self, i: usize, end: usize) -> Doc {
+        let mut docs = Vec::new();
+        let mut previous: Option<usize> = None;
+        let mut j = i;
+        while j < end {
+            if let Some(p) = previous {
+                if self.gap(p, j) {
+                    docs.push(text(" "));
+                }
+            }
+            docs.push(self.comments(j));
+            match self.token(j) {
+                Token::Punct("{") if self.block[j] => docs.push(self.block(j)),
+                Token::Punct("(" | "[" | "{") => docs.push(self.brackets(j)),
+                _ => {
+                    docs.push(text(&self.render(j)));
+                    previous = Some(j);
+                    j += 1;
+                    continue;
+                }
+            }
+            previous = Some(self.close[j]);
+            j = self.close[j] + 1;
+        }
+        Doc::Concat(docs)
+    }
+
+    /// Brackets hug a single item, except for the condition of an `if` or the value of a `return`, which goes on a
+    /// line of its own if it doesn't fit. Several items are put one per line if they don't fit.
+    fn brackets(&self, i: usize) -> Doc {
+        let close = self.close[i];
+        let (open, shut, padding) = match self.token(i) {
+            Token::Punct("{") => ("{", "}", " "),
+            Token::Punct("[") => ("[", "]", ""),
+            _ => ("(", ")", ""),
+        };
+        let separator = if i > 0 && self.is_word(i - 1, i, &["for"]) {
+            ";"
+        } else {
+            ","
+        };
+        let items: Vec<(usize, usize)> = self
+            .split(i + 1, close, &[separator])
+            .into_iter()
+            .filter(|(start, stop)| start < stop)
+            .collect();
+        if items.is_empty() {
+            return text(&format!("{open}{shut}"));
+        }
+        let head = i > 0 && self.is_word(i - 1, i, &["if", "while", "switch", "return"]);
+        if let [(start, stop)] = items[..] {
+            let broken = self.find(start, stop, &["&&", "||", "?"]).is_some();
+            if open != "{" && !(head && broken) {
+                return Doc::Concat(vec![text(open), self.expression(start, stop, true), text(shut)]);
+            }
+            if broken && self.is_word(i - 1, i, &["return"]) {
+                // The parentheses of a returned expression are only needed to break it over lines.
+                return Doc::Group(Box::new(Doc::Concat(vec![
+                    Doc::IfBreak("("),
+                    Doc::Indent(Box::new(Doc::Concat(vec![
+                        Doc::Line(""),
+                        self.expression(start, stop, false),
+                    ]))),
+                    Doc::Line(""),
+                    Doc::IfBreak(")"),
+                ])));
+            }
+        }
+        let mut docs = vec![Doc::Line(padding)];
+        for (n, (start, stop)) in items.iter().enumerate() {
+            if n > 0 {
+                docs.push(text(separator));
+                docs.push(Doc::Line(" "));
+            }
+            docs.push(self.item(*start, *stop, open == "{"));
+        }
+        Doc::Group(Box::new(Doc::Concat(vec![
+            text(open),
+            Doc::Indent(Box::new(Doc::Concat(docs))),
+            Doc::Line(padding),
+            text(shut),
+        ])))
+    }
+
+    /// An item of a list, or a property if `object`.
+    fn item(&self, start: usize, stop: usize, object: bool) -> Doc {
+        let keyed =
+            object && matches!(self.token(start), Token::Word(_) | Token::Str(_)) && self.is(start + 1, stop, ":");
+        if keyed {
+            Doc::Concat(vec![
+                self.comments(start),
+                text(&self.render(start)),
+                text(": "),
+                self.expression(start + 2, stop, true),
+            ])
+        } else {
+            self.expression(start, stop, false)
+        }
+    }
+
+    /// Whether a space goes between the tokens at `a` and `b`.
+    fn gap(&self, a: usize, b: usize) -> bool {
+        if self.tokens[b].space {
+            return true;
+        }
+        match (self.token(a), self.token(b)) {
+            (_, Token::Punct("," | ";" | "." | ")" | "]" | "}" | ":" | "++" | "--")) => false,
+            (Token::Punct("." | "..." | "!" | "(" | "[" | "~"), _) => false,
+            (Token::Punct(p), _) if BINARY.contains(p) => !self.unary(a),
+            (_, Token::Punct(p)) if BINARY.contains(p) => true,
+            (Token::Punct(":" | ","), _) | (Token::Punct(")" | "}"), Token::Word(_)) => true,
+            (_, Token::Punct("{")) => true,
+            (Token::Word(w), Token::Punct("(" | "[") | Token::Str(_) | Token::Regex(_)) => {
+                KEYWORDS.contains(&w.as_str())
+            }
+            (Token::Word(_), _) => true,
+            _ => false,
+        }
+    }
+
+    /// Whether the `-` or `+` at `i` is a sign rather than an operator.
+    fn unary(&self, i: usize) -> bool {
+        matches!(self.token(i), Token::Punct("-" | "+"))
+            && (i == 0
+                || match self.token(i - 1) {
+                    Token::Punct(p) => !matches!(*p, ")" | "]" | "}"),
+                    Token::Word(w) => KEYWORDS.contains(&w.as_str()),
+                    _ => false,
+                })
+    }
+
+    /// The comments before the token at `i` that go on the same line, doc comments aside.
+    fn comments(&self, i: usize) -> Doc {
+        Doc::Concat(
+            self.tokens[i]
+                .comments
+                .iter()
+                .filter(|c| !c.text.starts_with("/**"))
+                .map(|c| parse_marker(&c.text).map_or_else(|| text(&c.text), Doc::Mark))
+                .collect(),
+        )
+    }
+
+    fn render(&self, i: usize) -> String {
+        match self.token(i) {
+            Token::Word(w) => w.clone(),
+            Token::Str(s) => quote(s, self.style.single_quote),
+            Token::Regex(r) => r.clone(),
+            Token::Punct(p) => p.to_string(),
+        }
+    }
+}
+
+fn text(string: &str) -> Doc {
+    Doc::Text(string.to_string())
+}
+
+/// Operands joined by `op`, one per line if they don't fit.
+fn chain(operands: Vec<Doc>, op: &str, indent: bool) -> Doc {
+    let mut operands = operands.into_iter();
+    let first = operands.next().unwrap();
+    let mut rest = Vec::new();
+    for operand in operands {
+        rest.push(text(&format!(" {op}")));
+        rest.push(Doc::Line(" "));
+        rest.push(operand);
+    }
+    let rest = if indent {
+        Doc::Indent(Box::new(Doc::Concat(rest)))
+    } else {
+        Doc::Concat(rest)
+    };
+    Doc::Group(Box::new(Doc::Concat(vec![first, rest])))
+}
+
+/// The string literal in the preferred quotes, unless that takes more escapes.
+fn quote(literal: &str, single: bool) -> String {
+    let original = literal.chars().next().unwrap();
+    let inner = &literal[1..literal.len() - 1];
+    let (preferred, other) = if single { ('\'', '"') } else { ('"', '\'') };
+    let count = |q: char| inner.chars().filter(|c| *c == q).count();
+    let quote = if count(preferred) > count(other) {
+        other
+    } else {
+        preferred
+    };
+    if quote == original {
+        return literal.to_string();
+    }
+    let mut string = String::from(quote);
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(c) if c == original => string.push(c),
+                Some(c) => {
+                    string.push('\\');
+                    string.push(c);
+                }
+                None => string.push('\\'),
+            },
+            c if c == quote => {
+                string.push('\\');
+                string.push(c);
+            }
+            c => string.push(c),
+        }
+    }
+    string.push(quote);
+    string
+}
+
+fn layout(doc: &Doc, style: &Style, marks: &mut Vec<Mark>) -> String {
+    let unit = if style.use_tabs {
+        "\t".to_string()
+    } else {
+        " ".repeat(style.tab_width)
+    };
+    let mut string = String::new();
+    let (mut line, mut column) = (0, 0);
+    let mut stack: Vec<(usize, bool, &Doc)> = vec![(0, false, doc)];
+    while let Some((depth, flat, doc)) = stack.pop() {
+        match doc {
+            Doc::Text(s) => {
+                string.push_str(s);
+                column += s.chars().count();
+            }
+            Doc::Mark(offset) => marks.push(mark(&string, line, *offset)),
+            Doc::Line(s) if flat => {
+                string.push_str(s);
+                column += s.len();
+            }
+            Doc::IfBreak(_) if flat => {}
+            Doc::IfBreak(s) => {
+                string.push_str(s);
+                column += s.len();
+            }
+            Doc::Line(_) | Doc::Hard => {
+                string.truncate(string.trim_end_matches([' ', '\t']).len());
+                string.push('\n');
+                line += 1;
+                string.push_str(&unit.repeat(depth));
+                column = depth * style.tab_width;
+            }
+            Doc::Concat(docs) => stack.extend(docs.iter().rev().map(|d| (depth, flat, d))),
+            Doc::Indent(d) => stack.push((depth + 1, flat, d)),
+            Doc::Group(d) => {
+                let flat = flat || fits(style.print_width as isize - column as isize, (depth, true, d), &stack);
+                stack.push((depth, flat, d));
+            }
+        }
+    }
+    string
+}
+
+/// Whether `next` fits in `width` when printed flat, along with what follows it up to the next line break. A group
+/// holding a hard line break, like a function body in an expression, never does.
+fn fits(mut width: isize, next: (usize, bool, &Doc), rest: &[(usize, bool, &Doc)]) -> bool {
+    let mut stack = vec![next];
+    let mut rest = rest.iter().rev();
+    let mut in_next = true;
+    while width >= 0 {
+        let Some((depth, flat, doc)) = stack.pop().or_else(|| {
+            in_next = false;
+            rest.next().copied()
+        }) else {
+            return true;
+        };
+        match doc {
+            Doc::Text(s) => width -= s.chars().count() as isize,
+            Doc::Mark(_) => {}
+            Doc::Line(s) if flat => width -= s.len() as isize,
+            Doc::IfBreak(_) if flat => {}
+            Doc::IfBreak(s) => width -= s.len() as isize,
+            Doc::Hard => return !in_next,
+            Doc::Line(_) => return true,
+            Doc::Concat(docs) => stack.extend(docs.iter().rev().map(|d| (depth, flat, d))),
+            Doc::Indent(d) | Doc::Group(d) => stack.push((depth, flat, d)),
+        }
+    }
+    false
+}
@@ -0,0 +1,320 @@
+use crate::case::{pascal_case, snake_case};
+use crate::jsdoc::Doc;
+use crate::lexer::Type;
+use crate::parsers::{brand, discriminate, or_branches, unparen, Entry, Generic, Key, Value};
+
+const KEYWORDS: [&str; 35] = [
+    "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class", "continue", "def", "del",
+    "elif", "else", "except", "finally", "for", "from", "global", "if", "import", "in", "is", "lambda", "nonlocal",
+    "not", "or", "pass", "raise", "return", "try", "while", "with", "yield",
+];
+
+/// Translates the interfaces into `TypedDict` classes, or pydantic models if `pydantic`. Object literal types become
+/// classes of their own, named after the property that holds them. Requires Python 3.11.
+pub fn interfaces_to_python(interfaces: &[Entry], pydantic: bool) -> String {
+    let names: Vec<String> = interfaces
+        .iter()
+        .map(|i| match &i.key {
+            Key::Name(name) => name.clone(),
+            _ => panic!("Name of interface not found"),
+        })
+        .collect();
+    let mut python = Python {
+        pydantic,
+        taken: names.clone(),
+        names,
+        classes: Vec::new(),
+        aliases: Vec::new(),
+    };
+    let mut params: Vec<&String> = Vec::new();
+    for i in interfaces {
+        let Key::Name(name) = &i.key else { unreachable!() };
+        python.class(name, &i.params, &i.value, &i.doc);
+        for p in &i.params {
+            if !params.contains(&p) {
+                params.push(p);
+            }
+        }
+    }
+    let type_vars: String = params.iter().map(|p| format!("{p} = TypeVar(\"{p}\")\n")).collect();
+    let imports = if pydantic {
+        "from __future__ import annotations\n\nfrom typing import Annotated, Any, Generic, Literal, TypeVar\n\nfrom pydantic import BaseModel, ConfigDict, Field\n"
+    } else {
+        "from typing import Any, Generic, Literal, NotRequired, TypedDict, TypeVar\n"
+    };
+    let type_vars = if type_vars.is_empty() {
+        type_vars
+    } else {
+        format!("\n{type_vars}")
+    };
+    format!("{imports}{type_vars}\n\n{}", python.classes.join("\n\n"))
+}
+
+struct Python {
+    pydantic: bool,
+    names: Vec<String>,
+    /// Names of the interfaces and of every class generated so far.
+    taken: Vec<String>,
+    /// Declarations in output order.
+    classes: Vec<String>,
+    /// Type generated for each alias, so every use of an alias shares its classes.
+    aliases: Vec<(String, String)>,
+}
+
+impl Python {
+    fn class(&mut self, name: &str, params: &[String], props: &[Value], doc: &Option<Doc>) {
+        let props: Vec<(&Entry, &String)> = props
+            .iter()
+            .filter_map(|i| match i {
+                Value::Entry(
+                    p @ Entry {
+                        key: Key::Name(n) | Key::Optional(n),
+                        ..
+                    },
+                ) => Some((p, n)),
+                _ => None,
+            })
+            .collect();
+        let fields: Vec<(String, String, bool, &Option<Doc>)> = props
+            .iter()
+            .map(|(p, n)| {
+                let ty = self.values(&p.value, &format!("{name}{}", pascal_case(n)));
+                (n.to_string(), ty, matches!(p.key, Key::Optional(_)), &p.doc)
+            })
+            .collect();
+        let generic = if params.is_empty() {
+            String::new()
+        } else {
+            format!(", Generic[{}]", params.join(", "))
+        };
+        let class_doc = match doc {
+            Some(doc) if !doc.description.is_empty() => format!("    {}\n", docstring(&doc.description)),
+            _ => String::new(),
+        };
+        let class = if self.pydantic {
+            let body: String = fields
+                .iter()
+                .map(|(n, ty, optional, doc)| {
+                    let ident = identifier(n);
+                    let mut args = Vec::new();
+                    if ident != *n {
+                        args.push(format!("alias={}", python_string(n)));
+                    }
+                    if let Some(doc) = doc.as_ref().filter(|d| !d.description.is_empty()) {
+                        args.push(format!("description={}", python_string(&doc.description)));
+                    }
+                    let nullable = if ty == "None" || ty.ends_with(" | None") {
+                        ty.clone()
+                    } else {
+                        format!("{ty} | None")
+                    };
+                    let (ty, default) = match (optional, args.is_empty()) {
+                        (true, true) => (nullable, " = None".to_string()),
+                        (true, false) => (nullable, format!(" = Field(None, {})", args.join(", "))),
+                        (false, true) => (ty.clone(), String::new()),
+                        (false, false) => (ty.clone(), format!(" = Field({})", args.join(", "))),
+                    };
+                    format!("    {ident}: {ty}{default}\n")
+                })
+                .collect();
+            format!(
+                "class {name}(BaseModel{generic}):\n{class_doc}    model_config = ConfigDict(extra=\"forbid\")\n\n{body}"
+            )
+        } else if fields.iter().all(|(n, ..)| identifier(n) == *n) {
+            let body: String = fields
+                .iter()
+                .map(|(n, ty, optional, doc)| {
+                    let ty = self.annotation(ty, *optional);
+                    let doc = match doc {
+                        Some(doc) if !doc.description.is_empty() => format!("    {}\n", docstring(&doc.description)),
+                        _ => String::new(),
+                    };
+                    format!("    {n}: {ty}\n{doc}")
+                })
+                .collect();
+            let body = if body.is_empty() && class_doc.is_empty() {
+                "    pass\n".to_string()
+            } else {
+                body
+            };
+            format!("class {name}(TypedDict{generic}):\n{class_doc}{body}")
+        } else {
+            // Keys that aren't identifiers need the functional syntax, which has no room for docstrings.
+            let body: String = fields
+                .iter()
+                .map(|(n, ty, optional, _)| {
+                    format!("        {}: {},\n", python_string(n), self.annotation(ty, *optional))
+                })
+                .collect();
+            format!("{name} = TypedDict(\n    \"{name}\",\n    {{\n{body}    }},\n)\n")
+        };
+        self.classes.push(class);
+    }
+
+    /// `TypedDict` tells optional keys by their `NotRequired` annotation when the class is created, so annotations
+    /// can't be postponed as a whole. Only types naming classes, which may be declared further down, are quoted.
+    fn annotation(&self, ty: &str, optional: bool) -> String {
+        let names_class = ty
+            .split(|c: char| !c.is_alphanumeric() && c != '_')
+            .any(|i| self.taken.iter().any(|n| n == i));
+        let ty = if names_class {
+            format!("'{}'", ty.replace('\\', "\\\\").replace('\'', "\\'"))
+        } else {
+            ty.to_string()
+        };
+        if optional {
+            format!("NotRequired[{ty}]")
+        } else {
+            ty
+        }
+    }
+
+    /// The type of all `values`, which are expected to be one type.
+    fn values(&mut self, values: &[Value], hint: &str) -> String {
+        let values: Vec<&Value> = values
+            .iter()
+            .filter(|i| !matches!(i, Value::Type(Type::Oper(_) | Type::Punct(_))))
+            .collect();
+        match values[..] {
+            [value] => self.ty(value, hint),
+            _ => "Any".to_string(),
+        }
+    }
+
+    /// `hint` names the class generated for an object literal type.
+    fn ty(&mut self, value: &Value, hint: &str) -> String {
+        match value {
+            Value::Type(t) => match t {
+                Type::String => "str".to_string(),
+                Type::Number => "float".to_string(),
+                Type::Boolean => "bool".to_string(),
+                Type::BigInt => "int".to_string(),
+                Type::Null | Type::Undefined => "None".to_string(),
+                Type::Custom(name) if name == "Date" => "str".to_string(),
+                Type::Object => "dict[str, Any]".to_string(),
+                Type::Param(p) => p.clone(),
+                Type::NumberLit(_) if literal(t).is_none() => "float".to_string(),
+                t => literal(t).map_or("Any".to_string(), |l| format!("Literal[{l}]")),
+            },
+            Value::Entry(e) => match &e.key {
+                Key::None => {
+                    let name = self.fresh(hint);
+                    self.class(&name, &[], &e.value, &None);
+                    name
+                }
+                Key::Ref(n) if self.names.contains(n) => n.clone(),
+                Key::Ref(n) => match self.aliases.iter().find(|(a, _)| a == n) {
+                    Some((_, ty)) => ty.clone(),
+                    None => {
+                        let ty = self.values(&e.value, n);
+                        self.aliases.push((n.clone(), ty.clone()));
+                        ty
+                    }
+                },
+                Key::Name(_) | Key::Optional(_) | Key::Paren | Key::Alias(_) => self.values(&e.value, hint),
+                Key::Generic(Generic::Array) => format!("list[{}]", self.values(&e.value, &format!("{hint}Item"))),
+                Key::Generic(Generic::Ref(n)) => {
+                    let args: Vec<String> = e.value.iter().map(|i| self.ty(i, hint)).collect();
+                    format!("{n}[{}]", args.join(", "))
+                }
+                Key::Generic(Generic::Custom(_)) => "Any".to_string(),
+                Key::Tuple => {
+                    let items: Vec<String> = e
+                        .value
+                        .iter()
+                        .enumerate()
+                        .map(|(i, v)| self.ty(v, &format!("{hint}{i}")))
+                        .collect();
+                    format!("tuple[{}]", items.join(", "))
+                }
+                Key::Or => self.union(e, hint),
+                Key::And => match brand(e) {
+                    Some(b) => self.ty(&b.base, hint),
+                    None => "Any".to_string(),
+                },
+            },
+        }
+    }
+
+    /// Literal members are collected into one `Literal[...]`. Pydantic dispatches discriminated unions on the tag.
+    fn union(&mut self, e: &Entry, hint: &str) -> String {
+        let branches = or_branches(e);
+        let mut literals: Vec<String> = Vec::new();
+        let mut types: Vec<String> = Vec::new();
+        for (i, branch) in branches.iter().enumerate() {
+            match unparen(branch) {
+                Value::Type(t) if literal(t).is_some() => literals.push(literal(t).unwrap()),
+                _ => {
+                    let ty = self.ty(branch, &format!("{hint}{}", i + 1));
+                    if !types.contains(&ty) {
+                        types.push(ty);
+                    }
+                }
+            }
+        }
+        if !literals.is_empty() {
+            types.insert(0, format!("Literal[{}]", literals.join(", ")));
+        }
+        let union = types.join(" | ");
+        match discriminate(branches) {
+            Some(d) if self.pydantic && d.others.is_empty() => {
+                format!("Annotated[{union}, Field(discriminator={})]", python_string(&d.key))
+            }
+            _ => union,
+        }
+    }
+
+    /// `hint`, or `hint` followed by a number if that is taken.
+    fn fresh(&mut self, hint: &str) -> String {
+        let mut name = hint.to_string();
+        let mut i = 2;
+        while self.taken.contains(&name) {
+            name = format!("{hint}{i}");
+            i += 1;
+        }
+        self.taken.push(name.clone());
+        name
+    }
+}
+
+/// The `Literal[...]` member for a literal type. Only integers are allowed as number literals.
+fn literal(t: &Type) -> Option<String> {
+    match t {
+        Type::StringLit(s) => Some(python_string(s)),
+        Type::NumberLit(n) => {
+            let n = n.replace('_', "");
+            n.parse::<i64>().is_ok().then_some(n)
+        }
+        Type::True => Some("True".to_string()),
+        Type::False => Some("False".to_string()),
+        _ => None,
+    }
+}
+
+/// `name` if it is a valid attribute name, its snake case otherwise.
+pub fn identifier(name: &str) -> String {
+    let valid = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if valid && !KEYWORDS.contains(&name) {
+        return name.to_string();
+    }
+    let snake = snake_case(name);
+    match snake.as_str() {
+        "" => "field".to_string(),
+        s if KEYWORDS.contains(&s) => format!("{s}_"),
+        s if s.starts_with(|c: char| c.is_ascii_digit()) => format!("field_{s}"),
+        _ => snake,
+    }
+}
+
+/// Every quote is escaped, so a description ending in `"` doesn't run into the closing `"""`.
+fn docstring(description: &str) -> String {
+    format!("\"\"\"{}\"\"\"", description.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn python_string(string: &str) -> String {
+    format!(
+        "\"{}\"",
+        string.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+    )
+}
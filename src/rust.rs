@@ -0,0 +1,373 @@
+use crate::case::{self, pascal_case};
+use crate::jsdoc::Doc;
+use crate::lexer::Type;
+use crate::parsers::{brand, discriminate, or_branches, unparen, Entry, Generic, Key, Value};
+
+/// Strict and reserved keywords from the Rust reference.
+const KEYWORDS: [&str; 52] = [
+    "as", "break", "const", "continue", "crate", "else", "enum", "extern", "false", "fn", "for", "if", "impl", "in",
+    "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return", "self", "Self", "static", "struct", "super",
+    "trait", "true", "type", "unsafe", "use", "where", "while", "async", "await", "dyn", "abstract", "become", "box",
+    "do", "final", "gen", "macro", "override", "priv", "typeof", "unsized", "virtual", "yield", "try",
+];
+
+/// Translates the interfaces into serde `struct`s. Object literal types become structs and unions become enums of
+/// their own, named after the property that holds them.
+pub fn interfaces_to_rust(interfaces: &[Entry]) -> String {
+    let names: Vec<String> = interfaces
+        .iter()
+        .map(|i| match &i.key {
+            Key::Name(name) => name.clone(),
+            _ => panic!("Name of interface not found"),
+        })
+        .collect();
+    let mut rust = Rust {
+        taken: names.clone(),
+        names,
+        items: Vec::new(),
+        aliases: Vec::new(),
+        current: String::new(),
+    };
+    for i in interfaces {
+        let Key::Name(name) = &i.key else { unreachable!() };
+        rust.current = name.clone();
+        rust.structure(name, &i.params, &i.value, &i.doc);
+    }
+    format!("use serde::{{Deserialize, Serialize}};\n\n{}", rust.items.join("\n"))
+}
+
+struct Rust {
+    names: Vec<String>,
+    /// Declarations in output order.
+    items: Vec<String>,
+    /// Names of the interfaces and of every type generated so far.
+    taken: Vec<String>,
+    /// Type generated for each alias, so every use of an alias shares it.
+    aliases: Vec<(String, String)>,
+    /// The interface being translated, whose own uses must be boxed.
+    current: String,
+}
+
+impl Rust {
+    fn structure(&mut self, name: &str, params: &[String], props: &[Value], doc: &Option<Doc>) {
+        let generics = if params.is_empty() {
+            String::new()
+        } else {
+            format!("<{}>", params.join(", "))
+        };
+        let fields = self.fields(name, props, "    ", "pub ");
+        self.items.push(format!(
+            "{}#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]\n#[serde(deny_unknown_fields)]\npub struct {name}{generics} {{\n{fields}}}\n",
+            doc_comment(doc, "")
+        ));
+    }
+
+    /// Properties that snake case to the same field, like `fooBar` and `foo_bar`, get a numbered suffix after the
+    /// first, and all keep their JSON name with `rename`.
+    fn fields(&mut self, owner: &str, props: &[Value], indent: &str, visibility: &str) -> String {
+        let mut taken: Vec<String> = Vec::new();
+        props
+            .iter()
+            .filter_map(|i| match i {
+                Value::Entry(
+                    p @ Entry {
+                        key: Key::Name(n) | Key::Optional(n),
+                        ..
+                    },
+                ) => Some((p, n)),
+                _ => None,
+            })
+            .map(|(p, n)| {
+                let mut ty = self.values(&p.value, &format!("{owner}{}", pascal_case(n)));
+                let mut attrs = Vec::new();
+                let mut ident = snake_case(n);
+                let mut i = 2;
+                while taken.contains(&ident) {
+                    ident = format!("{}_{i}", snake_case(n).trim_start_matches("r#"));
+                    i += 1;
+                }
+                taken.push(ident.clone());
+                if ident.trim_start_matches("r#") != n {
+                    attrs.push(format!("rename = {}", rust_string(n)));
+                }
+                if let Key::Optional(_) = p.key {
+                    if !ty.starts_with("Option<") {
+                        ty = format!("Option<{ty}>");
+                    }
+                    attrs.push("default, skip_serializing_if = \"Option::is_none\"".to_string());
+                }
+                let attrs = if attrs.is_empty() {
+                    String::new()
+                } else {
+                    format!("{indent}#[serde({})]\n", attrs.join(", "))
+                };
+                format!(
+                    "{}{attrs}{indent}{visibility}{ident}: {ty},\n",
+                    doc_comment(&p.doc, indent)
+                )
+            })
+            .collect()
+    }
+
+    /// The type of all `values`, which are expected to be one type.
+    fn values(&mut self, values: &[Value], hint: &str) -> String {
+        let values: Vec<&Value> = values
+            .iter()
+            .filter(|i| !matches!(i, Value::Type(Type::Oper(_) | Type::Punct(_))))
+            .collect();
+        match values[..] {
+            [value] => self.ty(value, hint, false),
+            _ => "serde_json::Value".to_string(),
+        }
+    }
+
+    /// `hint` names the struct or enum generated for an anonymous type. Recursive uses are boxed unless `in_vec`.
+    fn ty(&mut self, value: &Value, hint: &str, in_vec: bool) -> String {
+        match value {
+            Value::Type(t) => match t {
+                Type::String => "String".to_string(),
+                Type::Number | Type::NumberLit(_) => "f64".to_string(),
+                Type::Boolean | Type::True | Type::False => "bool".to_string(),
+                Type::BigInt => "i128".to_string(),
+                Type::Null => "()".to_string(),
+                Type::Custom(name) if name == "Date" => "String".to_string(),
+                Type::Object => "serde_json::Map<String, serde_json::Value>".to_string(),
+                Type::Param(p) => p.clone(),
+                Type::StringLit(_) => self.literal_enum(std::slice::from_ref(t), hint),
+                _ => "serde_json::Value".to_string(),
+            },
+            Value::Entry(e) => match &e.key {
+                Key::None => {
+                    let name = self.fresh(hint);
+                    self.structure(&name, &[], &e.value, &None);
+                    name
+                }
+                Key::Ref(n) if self.names.contains(n) => {
+                    if !in_vec && (e.value.is_empty() || refers_to(&e.value, &self.current)) {
+                        format!("Box<{n}>")
+                    } else {
+                        n.clone()
+                    }
+                }
+                Key::Ref(n) => match self.aliases.iter().find(|(a, _)| a == n) {
+                    Some((_, ty)) => ty.clone(),
+                    None => {
+                        let ty = self.values(&e.value, n);
+                        self.aliases.push((n.clone(), ty.clone()));
+                        ty
+                    }
+                },
+                Key::Name(_) | Key::Optional(_) | Key::Paren | Key::Alias(_) => match &e.value[..] {
+                    [value] => self.ty(value, hint, in_vec),
+                    values => self.values(values, hint),
+                },
+                Key::Generic(Generic::Array) => {
+                    let item = match &e.value[..] {
+                        [value] => self.ty(value, &format!("{hint}Item"), true),
+                        _ => "serde_json::Value".to_string(),
+                    };
+                    format!("Vec<{item}>")
+                }
+                Key::Generic(Generic::Ref(n)) => {
+                    let args: Vec<String> = e.value.iter().map(|i| self.ty(i, hint, false)).collect();
+                    if in_vec {
+                        format!("{n}<{}>", args.join(", "))
+                    } else {
+                        format!("Box<{n}<{}>>", args.join(", "))
+                    }
+                }
+                Key::Generic(Generic::Custom(_)) => "serde_json::Value".to_string(),
+                Key::Tuple => {
+                    let items: Vec<String> = e
+                        .value
+                        .iter()
+                        .enumerate()
+                        .map(|(i, v)| self.ty(v, &format!("{hint}{i}"), in_vec))
+                        .collect();
+                    match &items[..] {
+                        [item] => format!("({item},)"),
+                        items => format!("({})", items.join(", ")),
+                    }
+                }
+                Key::Or => self.union(e, hint, in_vec),
+                Key::And => match brand(e) {
+                    Some(b) => self.ty(&b.base, hint, in_vec),
+                    None => "serde_json::Value".to_string(),
+                },
+            },
+        }
+    }
+
+    fn union(&mut self, e: &Entry, hint: &str, in_vec: bool) -> String {
+        let (nulls, branches): (Vec<Value>, Vec<Value>) = or_branches(e)
+            .into_iter()
+            .partition(|i| matches!(unparen(i), Value::Type(Type::Null | Type::Undefined)));
+        let literals: Option<Vec<Type>> = branches
+            .iter()
+            .map(|i| match unparen(i) {
+                Value::Type(t @ (Type::StringLit(_) | Type::NumberLit(_) | Type::True | Type::False)) => {
+                    Some(t.clone())
+                }
+                _ => None,
+            })
+            .collect();
+        let ty = match (&branches[..], literals) {
+            ([], _) => "()".to_string(),
+            ([branch], _) => self.ty(branch, hint, in_vec),
+            (_, Some(l)) if l.iter().all(|i| matches!(i, Type::StringLit(_))) => self.literal_enum(&l, hint),
+            (_, Some(l)) if l.iter().all(|i| matches!(i, Type::NumberLit(_))) => "f64".to_string(),
+            (_, Some(l)) if l.iter().all(|i| matches!(i, Type::True | Type::False)) => "bool".to_string(),
+            _ => match discriminate(branches.clone()) {
+                Some(d)
+                    if d.others.is_empty()
+                        && d.cases
+                            .iter()
+                            .all(|(l, _)| l.iter().all(|i| matches!(i, Type::StringLit(_)))) =>
+                {
+                    self.tagged_enum(&d.key, &d.cases, hint)
+                }
+                _ => self.untagged_enum(&branches, hint),
+            },
+        };
+        if nulls.is_empty() || ty == "()" {
+            ty
+        } else {
+            format!("Option<{ty}>")
+        }
+    }
+
+    fn literal_enum(&mut self, literals: &[Type], hint: &str) -> String {
+        let name = self.fresh(hint);
+        let mut used: Vec<String> = Vec::new();
+        let mut variants = String::new();
+        for (i, literal) in literals.iter().enumerate() {
+            let Type::StringLit(s) = literal else { continue };
+            let mut variant = pascal_case(s);
+            if used.contains(&variant) {
+                variant = format!("{variant}{i}");
+            }
+            variants.push_str(&format!("    #[serde(rename = {})]\n    {variant},\n", rust_string(s)));
+            used.push(variant);
+        }
+        self.items.push(format!(
+            "#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]\npub enum {name} {{\n{variants}}}\n"
+        ));
+        name
+    }
+
+    /// An internally tagged enum with one struct variant per member, holding its properties other than the tag.
+    fn tagged_enum(&mut self, key: &str, cases: &[(Vec<Type>, Value)], hint: &str) -> String {
+        let name = self.fresh(hint);
+        let mut variants = String::new();
+        for (labels, object) in cases {
+            let Value::Entry(object) = unparen(object) else {
+                continue;
+            };
+            let props: Vec<Value> = object
+                .value
+                .iter()
+                .filter(|i| !matches!(i, Value::Entry(Entry { key: Key::Name(n), .. }) if n == key))
+                .cloned()
+                .collect();
+            let names: Vec<&String> = labels
+                .iter()
+                .filter_map(|i| match i {
+                    Type::StringLit(s) => Some(s),
+                    _ => None,
+                })
+                .collect();
+            let variant = pascal_case(names[0]);
+            let aliases: String = names[1..]
+                .iter()
+                .map(|i| format!(", alias = {}", rust_string(i)))
+                .collect();
+            let fields = self.fields(&format!("{name}{variant}"), &props, "        ", "");
+            variants.push_str(&format!(
+                "    #[serde(rename = {}{aliases})]\n    {variant} {{\n{fields}    }},\n",
+                rust_string(names[0])
+            ));
+        }
+        self.items.push(format!(
+            "#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]\n#[serde(tag = {}, deny_unknown_fields)]\npub enum {name} {{\n{variants}}}\n",
+            rust_string(key)
+        ));
+        name
+    }
+
+    /// Members are tried in order, so each variant is named after its type.
+    fn untagged_enum(&mut self, branches: &[Value], hint: &str) -> String {
+        let name = self.fresh(hint);
+        let mut used: Vec<String> = Vec::new();
+        let mut variants = String::new();
+        for (i, branch) in branches.iter().enumerate() {
+            let ty = self.ty(branch, &format!("{name}{i}"), false);
+            let mut variant = match ty.as_str() {
+                "String" => "String".to_string(),
+                "f64" => "Number".to_string(),
+                "bool" => "Bool".to_string(),
+                ty if ty.starts_with("Vec<") => "Array".to_string(),
+                ty if ty.starts_with('(') => "Tuple".to_string(),
+                ty if ty.starts_with("Box<") => ty[4..ty.len() - 1].to_string(),
+                ty if ty.chars().all(|c| c.is_alphanumeric() || c == '_') => ty.to_string(),
+                _ => format!("V{i}"),
+            };
+            if used.contains(&variant) {
+                variant = format!("{variant}{i}");
+            }
+            used.push(variant.clone());
+            variants.push_str(&format!("    {variant}({ty}),\n"));
+        }
+        self.items.push(format!(
+            "#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]\n#[serde(untagged)]\npub enum {name} {{\n{variants}}}\n"
+        ));
+        name
+    }
+
+    /// `hint`, or `hint` followed by a number if that is taken.
+    fn fresh(&mut self, hint: &str) -> String {
+        let mut name = hint.to_string();
+        let mut i = 2;
+        while self.taken.contains(&name) {
+            name = format!("{hint}{i}");
+            i += 1;
+        }
+        self.taken.push(name.clone());
+        name
+    }
+}
+
+/// Whether the expansion contains a use of `name`.
+fn refers_to(values: &[Value], name: &str) -> bool {
+    values.iter().any(|i| match i {
+        Value::Entry(Entry { key: Key::Ref(n), .. }) if n == name => true,
+        Value::Entry(e) => refers_to(&e.value, name),
+        Value::Type(_) => false,
+    })
+}
+
+fn doc_comment(doc: &Option<Doc>, indent: &str) -> String {
+    match doc {
+        Some(doc) if !doc.description.is_empty() => doc
+            .description
+            .lines()
+            .map(|i| format!("{indent}/// {i}\n").replace("/// \n", "///\n"))
+            .collect(),
+        _ => String::new(),
+    }
+}
+
+/// `string` in snake case, as a field name: keywords are escaped as raw identifiers.
+pub fn snake_case(string: &str) -> String {
+    let name = case::snake_case(string);
+    match name.as_str() {
+        "" => "field".to_string(),
+        "self" | "super" | "crate" => format!("{name}_"),
+        n if KEYWORDS.contains(&n) => format!("r#{name}"),
+        n if n.starts_with(|c: char| c.is_ascii_digit()) => format!("_{name}"),
+        _ => name,
+    }
+}
+
+fn rust_string(string: &str) -> String {
+    format!("\"{}\"", string.replace('\\', "\\\\").replace('"', "\\\""))
+}
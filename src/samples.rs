@@ -0,0 +1,599 @@
+use crate::cmd::{relative_module, Options};
+use crate::js::{generated_file, js_string, source_string};
+use crate::jsdoc::{is_number_literal, Constraint, Format};
+use crate::lexer::Type;
+use crate::parsers::{brand, literals, Entry, Generic, Key, Value};
+
+/// A value made up to test a guard, rendered as JavaScript by `Sample::js`.
+#[derive(Clone)]
+enum Sample {
+    /// A primitive of the given `typeof`, and whether it is the value of a literal type.
+    Primitive(&'static str, String, bool),
+    Object(Vec<(String, Sample)>),
+    Array(Vec<Sample>),
+}
+
+impl Sample {
+    fn js(&self) -> String {
+        match self {
+            Sample::Primitive(_, js, _) => js.clone(),
+            Sample::Object(properties) => {
+                let properties: Vec<String> = properties
+                    .iter()
+                    .map(|(name, value)| format!("{}:{}", object_key(name), value.js()))
+                    .collect();
+                format!("{{{}}}", properties.join(","))
+            }
+            Sample::Array(items) => format!("[{}]", items.iter().map(Sample::js).collect::<Vec<_>>().join(",")),
+        }
+    }
+}
+
+/// A value the guard has to reject, because of `what` at `path` (relative to the value it is part of).
+struct Invalid {
+    path: String,
+    what: &'static str,
+    value: Sample,
+}
+
+struct Sampled {
+    valid: Sample,
+    invalid: Vec<Invalid>,
+    /// Whether `valid` is known to pass the guard. When a constraint such as `@pattern` can't be met, the value of the
+    /// property is left as is: the guard may reject `valid`, but still has to reject every value in `invalid`, which
+    /// are wrong elsewhere.
+    verified: bool,
+}
+
+struct Sampler<'a> {
+    interfaces: &'a [Entry],
+    options: &'a Options,
+    /// The interfaces being sampled, so a recursive reference isn't followed forever.
+    stack: Vec<String>,
+}
+
+/// The first of these that a type doesn't accept replaces the value as a wrong primitive.
+const PROBES: [(&str, &str); 5] = [
+    ("string", "\"wrong\""),
+    ("number", "0"),
+    ("boolean", "true"),
+    ("object", "{}"),
+    ("null", "null"),
+];
+
+/// The longest string or array made up to meet a `@minLength` or `@minItems`.
+const MAX_LENGTH: f64 = 10_000.0;
+
+/// A `node:test` file with, per interface, a valid value the guard must accept and values mutated from it that the
+/// guard must reject: a missing required key, a wrong primitive, a wrong literal or a tuple of the wrong length.
+/// Properties take their `@example` as value if they have one. The test of the valid value is skipped if a constraint
+/// can't be met otherwise, e.g. a `@pattern`, and interfaces no value can be made up for at all, e.g. because of a
+/// recursive required property, get a skipped test.
+pub fn interfaces_to_tests(interfaces: &[Entry], options: &Options) -> String {
+    let mut sampler = Sampler {
+        interfaces,
+        options,
+        stack: Vec::new(),
+    };
+    let mut guards: Vec<String> = Vec::new();
+    let mut tests = String::new();
+    for i in interfaces {
+        let Key::Name(name) = &i.key else {
+            panic!("Name of interface not found")
+        };
+        let guard = options.naming.guard(name);
+        // Type parameters are checked by guards passed in, which accept anything here.
+        let call = |value: &Sample| {
+            let params: String = i.params.iter().map(|_| ",anything").collect();
+            format!("{guard}({}{params})", value.js())
+        };
+        let skip = |title: String| format!("test.skip({},()=>{{}});\n", js_string(&title));
+        let Some(sampled) = sampler.interface(name) else {
+            tests.push_str(&skip(format!("{guard} (no valid {name} could be made up)")));
+            continue;
+        };
+        guards.push(guard.clone());
+        let test = |title: String, value: &Sample, expected: bool| {
+            format!(
+                "test({},()=>assert.equal({},{expected}));\n",
+                js_string(&title),
+                call(value)
+            )
+        };
+        tests.push_str(&if sampled.verified {
+            test(format!("{guard} accepts a valid {name}"), &sampled.valid, true)
+        } else {
+            skip(format!("{guard} accepts a valid {name} (none could be made up)"))
+        });
+        for i in &sampled.invalid {
+            tests.push_str(&test(
+                format!("{guard} rejects a {} at ${}", i.what, i.path),
+                &i.value,
+                false,
+            ));
+        }
+    }
+    let path = tests_path(options);
+    let guards_module = if options.out_dir {
+        "./index".to_string()
+    } else {
+        relative_module(&path, &options.write_path)
+    };
+    let mut module = "import { test } from \"node:test\";\nimport assert from \"node:assert/strict\";\n".to_string();
+    if !guards.is_empty() {
+        module.push_str(&format!(
+            "import {{ {} }} from {};\n",
+            guards.join(", "),
+            js_string(&guards_module)
+        ));
+    }
+    if interfaces.iter().any(|i| !i.params.is_empty()) {
+        module.push_str("\nconst anything=(x: unknown): x is unknown=>true;\n");
+    }
+    module.push('\n');
+    module.push_str(&tests);
+    let options = Options {
+        write_path: path,
+        ..options.clone()
+    };
+    generated_file(module, &options)
+}
+
+/// `guards.test.ts` next to `guards.ts` or `guards.js`, or `index.test.ts` in the output directory.
+pub fn tests_path(options: &Options) -> String {
+    let write_path = &options.write_path;
+    if options.out_dir {
+        format!("{}/index.test.ts", write_path.trim_end_matches(['/', '\\']))
+    } else {
+        let stem = write_path
+            .strip_suffix(".ts")
+            .or_else(|| write_path.strip_suffix(".js"))
+            .unwrap_or(write_path);
+        format!("{stem}.test.ts")
+    }
+}
+
+impl Sampler<'_> {
+    fn interface(&mut self, name: &str) -> Option<Sampled> {
+        if self.stack.iter().any(|i| i == name) {
+            return None;
+        }
+        let entry = self
+            .interfaces
+            .iter()
+            .find(|i| matches!(&i.key, Key::Name(n) if n == name))?;
+        self.stack.push(name.to_string());
+        let sampled = self.object(&entry.value);
+        self.stack.pop();
+        let mut sampled = sampled?;
+        sampled.invalid.push(wrong_primitive(&["object"]));
+        Some(sampled)
+    }
+
+    fn values(&mut self, values: &[Value]) -> Option<Sampled> {
+        match values {
+            [value] => self.value(value),
+            _ => None,
+        }
+    }
+
+    fn value(&mut self, value: &Value) -> Option<Sampled> {
+        let mut sampled = match value {
+            Value::Type(t) => Sampled {
+                valid: self.primitive(t)?,
+                invalid: Vec::new(),
+                verified: true,
+            },
+            Value::Entry(e) => match &e.key {
+                Key::Ref(n) if e.value.is_empty() => return self.interface(n),
+                Key::Paren | Key::Alias(_) | Key::Ref(_) => return self.values(&e.value),
+                Key::None => self.object(&e.value)?,
+                Key::Generic(Generic::Array) => {
+                    let item = self.values(&e.value)?;
+                    Sampled {
+                        valid: Sample::Array(vec![item.valid]),
+                        invalid: item
+                            .invalid
+                            .into_iter()
+                            .map(|i| Invalid {
+                                path: format!("[0]{}", i.path),
+                                what: i.what,
+                                value: Sample::Array(vec![i.value]),
+                            })
+                            .collect(),
+                        verified: item.verified,
+                    }
+                }
+                Key::Tuple => self.tuple(&e.value)?,
+                // Mutations of one branch may still match another, so only the union as a whole is mutated.
+                Key::Or => {
+                    let branches: Vec<Sampled> = e.value.iter().filter_map(|i| self.value(i)).collect();
+                    let branch = branches.into_iter().reduce(|a, b| if a.verified { a } else { b })?;
+                    Sampled {
+                        valid: branch.valid,
+                        invalid: Vec::new(),
+                        verified: branch.verified,
+                    }
+                }
+                Key::And => {
+                    let b = brand(e)?;
+                    match &b.name {
+                        Some(name) if self.options.brands.iter().any(|(brand, _)| brand == name) => return None,
+                        _ => return self.value(&b.base),
+                    }
+                }
+                Key::Name(_) | Key::Optional(_) | Key::Generic(Generic::Custom(_) | Generic::Ref(_)) => return None,
+            },
+        };
+        sampled.invalid.extend(wrong_literal(value));
+        if let Some(kinds) = kinds(value, self.options) {
+            sampled.invalid.push(wrong_primitive(&kinds));
+        }
+        Some(sampled)
+    }
+
+    fn primitive(&self, t: &Type) -> Option<Sample> {
+        let (kind, js) = match t {
+            Type::String => ("string", "\"text\"".to_string()),
+            Type::Number => ("number", "1".to_string()),
+            Type::Boolean | Type::True => ("boolean", "true".to_string()),
+            Type::False => ("boolean", "false".to_string()),
+            Type::StringLit(s) => return Some(Sample::Primitive("string", source_string(s), true)),
+            Type::NumberLit(n) => return Some(Sample::Primitive("number", n.clone(), true)),
+            Type::BigInt => ("bigint", "1n".to_string()),
+            Type::Symbol => ("symbol", "Symbol()".to_string()),
+            Type::Function => ("function", "()=>{}".to_string()),
+            Type::Undefined => ("undefined", "undefined".to_string()),
+            Type::Null | Type::Any | Type::Unknown | Type::Param(_) => ("null", "null".to_string()),
+            Type::Custom(name) if self.options.guards.iter().any(|(i, _)| i == name) => return None,
+            Type::Object | Type::Custom(_) => ("object", "{}".to_string()),
+            Type::Oper(_) | Type::Punct(_) => return None,
+        };
+        Some(Sample::Primitive(kind, js, matches!(t, Type::True | Type::False)))
+    }
+
+    fn object(&mut self, properties: &[Value]) -> Option<Sampled> {
+        // Optional keys are given too, as the guards compare the number of keys.
+        let mut valid: Vec<(String, Sample)> = Vec::new();
+        let mut required: Vec<usize> = Vec::new();
+        let mut mutated: Vec<(usize, Invalid)> = Vec::new();
+        let mut verified = true;
+        for p in properties {
+            let Value::Entry(e) = p else { return None };
+            let name = match &e.key {
+                Key::Name(n) => {
+                    required.push(valid.len());
+                    n
+                }
+                Key::Optional(n) => n,
+                _ => return None,
+            };
+            let constraints = e.doc.as_ref().map(|d| d.constraints()).unwrap_or_default();
+            let sampled = self.values(&e.value)?;
+            let index = valid.len();
+            let example = e.doc.as_ref().and_then(|d| d.example());
+            let sample = match example.and_then(|x| from_example(&sampled.valid, x)) {
+                Some(sample) => Some(sample),
+                None => constrain(sampled.valid.clone(), &constraints),
+            };
+            verified &= sampled.verified && sample.is_some();
+            valid.push((name.clone(), sample.unwrap_or(sampled.valid)));
+            mutated.extend(sampled.invalid.into_iter().map(|i| {
+                let path = format!("{}{}", path_key(name), i.path);
+                (index, Invalid { path, ..i })
+            }));
+        }
+        let mut invalid: Vec<Invalid> = required
+            .into_iter()
+            .map(|index| {
+                let mut properties = valid.clone();
+                let (name, _) = properties.remove(index);
+                Invalid {
+                    path: path_key(&name),
+                    what: "missing required key",
+                    value: Sample::Object(properties),
+                }
+            })
+            .collect();
+        invalid.extend(mutated.into_iter().map(|(index, i)| {
+            let mut properties = valid.clone();
+            properties[index].1 = i.value;
+            Invalid {
+                value: Sample::Object(properties),
+                ..i
+            }
+        }));
+        Some(Sampled {
+            valid: Sample::Object(valid),
+            invalid,
+            verified,
+        })
+    }
+
+    fn tuple(&mut self, elements: &[Value]) -> Option<Sampled> {
+        let mut valid: Vec<Sample> = Vec::new();
+        let mut mutated: Vec<(usize, Invalid)> = Vec::new();
+        let mut verified = true;
+        for (index, element) in elements.iter().enumerate() {
+            let sampled = self.value(element)?;
+            verified &= sampled.verified;
+            valid.push(sampled.valid);
+            mutated.extend(sampled.invalid.into_iter().map(|i| {
+                let path = format!("[{index}]{}", i.path);
+                (index, Invalid { path, ..i })
+            }));
+        }
+        let mut longer = valid.clone();
+        longer.push(
+            valid
+                .last()
+                .cloned()
+                .unwrap_or(Sample::Primitive("null", "null".to_string(), false)),
+        );
+        let mut invalid = vec![Invalid {
+            path: String::new(),
+            what: "wrong tuple length (one too many)",
+            value: Sample::Array(longer),
+        }];
+        if !valid.is_empty() {
+            invalid.push(Invalid {
+                path: String::new(),
+                what: "wrong tuple length (one too few)",
+                value: Sample::Array(valid[..valid.len() - 1].to_vec()),
+            });
+        }
+        invalid.extend(mutated.into_iter().map(|(index, i)| {
+            let mut elements = valid.clone();
+            elements[index] = i.value;
+            Invalid {
+                value: Sample::Array(elements),
+                ..i
+            }
+        }));
+        Some(Sampled {
+            valid: Sample::Array(valid),
+            invalid,
+            verified,
+        })
+    }
+}
+
+/// The `typeof` (or `null`) of the values `value` accepts, or `None` if it accepts anything or can't tell.
+fn kinds(value: &Value, options: &Options) -> Option<Vec<&'static str>> {
+    Some(match value {
+        Value::Type(t) => vec![match t {
+            Type::String | Type::StringLit(_) => "string",
+            Type::Number | Type::NumberLit(_) => "number",
+            Type::Boolean | Type::True | Type::False => "boolean",
+            Type::BigInt => "bigint",
+            Type::Symbol => "symbol",
+            Type::Function => "function",
+            Type::Undefined => "undefined",
+            Type::Null => "null",
+            Type::Custom(name) if options.guards.iter().any(|(i, _)| i == name) => return None,
+            Type::Object | Type::Custom(_) => "object",
+            _ => return None,
+        }],
+        Value::Entry(e) => match &e.key {
+            Key::None | Key::Tuple | Key::Generic(Generic::Array) => vec!["object"],
+            Key::Ref(_) if e.value.is_empty() => vec!["object"],
+            Key::Or | Key::Paren | Key::Alias(_) | Key::Ref(_) => {
+                let mut all = Vec::new();
+                for i in &e.value {
+                    all.extend(kinds(i, options)?);
+                }
+                all
+            }
+            Key::And => kinds(&brand(e)?.base, options)?,
+            _ => return None,
+        },
+    })
+}
+
+fn wrong_primitive(kinds: &[&str]) -> Invalid {
+    let (kind, js) = PROBES.iter().find(|(kind, _)| !kinds.contains(kind)).unwrap();
+    Invalid {
+        path: String::new(),
+        what: "wrong primitive",
+        value: Sample::Primitive(kind, js.to_string(), false),
+    }
+}
+
+/// For a type made only of literals, e.g. `"a" | "b"`, a literal of the same type as its first one other than `null`,
+/// but none of its literals.
+fn wrong_literal(value: &Value) -> Option<Invalid> {
+    let literals = literals(value)?;
+    let value = match literals.iter().find(|i| **i != Type::Null)? {
+        Type::StringLit(_) => {
+            let string = (0..)
+                .map(|n| format!("wrong{n}"))
+                .find(|s| !literals.contains(&Type::StringLit(s.clone())))?;
+            Sample::Primitive("string", js_string(&string), true)
+        }
+        Type::NumberLit(_) => {
+            let numbers: Vec<f64> = literals
+                .iter()
+                .filter_map(|i| match i {
+                    Type::NumberLit(n) => n.parse::<f64>().ok(),
+                    _ => None,
+                })
+                .collect();
+            let max = numbers.iter().copied().reduce(f64::max)?;
+            Sample::Primitive("number", number(max.floor() + 1.0), true)
+        }
+        _ if literals.contains(&Type::True) && literals.contains(&Type::False) => return None,
+        _ if literals.contains(&Type::True) => Sample::Primitive("boolean", "false".to_string(), true),
+        _ => Sample::Primitive("boolean", "true".to_string(), true),
+    };
+    Some(Invalid {
+        path: String::new(),
+        what: "wrong literal",
+        value,
+    })
+}
+
+/// The `@example` of a string or number property as its sample. Strings may be quoted.
+fn from_example(sample: &Sample, example: &str) -> Option<Sample> {
+    match sample {
+        Sample::Primitive("string", _, false) => {
+            // A quoted example is a string literal, whose escapes are kept, and anything else plain text.
+            let unquoted = ['"', '\'']
+                .iter()
+                .find_map(|q| example.strip_prefix(*q)?.strip_suffix(*q));
+            Some(Sample::Primitive(
+                "string",
+                unquoted.map_or_else(|| js_string(example), source_string),
+                false,
+            ))
+        }
+        Sample::Primitive("number", _, false) if is_number_literal(example) => {
+            Some(Sample::Primitive("number", example.to_string(), false))
+        }
+        _ => None,
+    }
+}
+
+/// Adjusts a sample to the JSDoc constraints of its property, or `None` if it can't be.
+fn constrain(sample: Sample, constraints: &[Constraint]) -> Option<Sample> {
+    let parse = |n: &String| n.parse::<f64>().unwrap();
+    match sample {
+        Sample::Primitive("number", js, literal) => {
+            let fits = |x: f64| {
+                constraints.iter().all(|c| match c {
+                    Constraint::Minimum(n) => x >= parse(n),
+                    Constraint::Maximum(n) => x <= parse(n),
+                    Constraint::ExclusiveMinimum(n) => x > parse(n),
+                    Constraint::ExclusiveMaximum(n) => x < parse(n),
+                    Constraint::Integer => x.fract() == 0.0,
+                    _ => true,
+                })
+            };
+            if literal {
+                return js
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|x| fits(*x))
+                    .map(|_| Sample::Primitive("number", js, true));
+            }
+            let candidates: Vec<f64> = constraints
+                .iter()
+                .flat_map(|c| match c {
+                    Constraint::Minimum(n) => vec![parse(n), parse(n).ceil()],
+                    Constraint::Maximum(n) => vec![parse(n), parse(n).floor()],
+                    Constraint::ExclusiveMinimum(n) => vec![parse(n).floor() + 1.0],
+                    Constraint::ExclusiveMaximum(n) => vec![parse(n).ceil() - 1.0],
+                    _ => vec![],
+                })
+                .chain([1.0])
+                .collect();
+            let x = candidates.into_iter().find(|x| fits(*x))?;
+            Some(Sample::Primitive("number", number(x), false))
+        }
+        Sample::Primitive("string", js, literal) => {
+            let string_constraint = |c: &Constraint| {
+                matches!(
+                    c,
+                    Constraint::MinLength(_)
+                        | Constraint::MaxLength(_)
+                        | Constraint::Pattern(_)
+                        | Constraint::Format(_)
+                )
+            };
+            if !constraints.iter().any(string_constraint) {
+                return Some(Sample::Primitive("string", js, literal));
+            }
+            if literal || constraints.iter().any(|c| matches!(c, Constraint::Pattern(_))) {
+                return None;
+            }
+            let string = match constraints.iter().find_map(|c| match c {
+                Constraint::Format(f) => Some(f),
+                _ => None,
+            }) {
+                Some(Format::Email) => "user@example.com".to_string(),
+                Some(Format::Uuid) => "123e4567-e89b-12d3-a456-426614174000".to_string(),
+                Some(Format::DateTime) => "2024-01-01T00:00:00Z".to_string(),
+                Some(Format::Uri) => "https://example.com".to_string(),
+                None => {
+                    let min = constraints.iter().find_map(|c| match c {
+                        Constraint::MinLength(n) => Some(parse(n).ceil()),
+                        _ => None,
+                    });
+                    match min {
+                        Some(min) if min > MAX_LENGTH => return None,
+                        Some(min) => "a".repeat(min.max(0.0) as usize),
+                        None => "aaaa".to_string(),
+                    }
+                }
+            };
+            let length = string.chars().count() as f64;
+            let fits = constraints.iter().all(|c| match c {
+                Constraint::MinLength(n) => length >= parse(n),
+                Constraint::MaxLength(n) => length <= parse(n),
+                _ => true,
+            });
+            fits.then(|| Sample::Primitive("string", js_string(&string), false))
+        }
+        Sample::Array(items) => {
+            let min = constraints.iter().find_map(|c| match c {
+                Constraint::MinItems(n) => Some(parse(n).ceil()),
+                _ => None,
+            });
+            let items = match min {
+                Some(min) if min > MAX_LENGTH => return None,
+                Some(min) if min as usize > items.len() => {
+                    let min = min as usize;
+                    if min > 1 && constraints.contains(&Constraint::UniqueItems) {
+                        return None;
+                    }
+                    vec![items[0].clone(); min]
+                }
+                _ => items,
+            };
+            let fits = constraints.iter().all(|c| match c {
+                Constraint::MaxItems(n) => items.len() as f64 <= parse(n),
+                _ => true,
+            });
+            fits.then_some(Sample::Array(items))
+        }
+        sample => Some(sample),
+    }
+}
+
+/// Renders a whole number without a fraction, e.g. `3` rather than `3.0`.
+fn number(x: f64) -> String {
+    if x.fract() == 0.0 && x.abs() < 1e15 {
+        format!("{}", x as i64)
+    } else {
+        x.to_string()
+    }
+}
+
+/// `__proto__` in an object literal sets the prototype, so it is given as a computed key to be an own property.
+fn object_key(name: &str) -> String {
+    if name == "__proto__" {
+        format!("[{}]", source_string(name))
+    } else if is_identifier(name) {
+        name.to_string()
+    } else {
+        source_string(name)
+    }
+}
+
+/// `.name`, or `["name"]` if `name` isn't an identifier.
+fn path_key(name: &str) -> String {
+    if is_identifier(name) {
+        format!(".{name}")
+    } else {
+        format!("[{}]", js_string(name))
+    }
+}
+
+fn is_identifier(string: &str) -> bool {
+    let mut chars = string.chars();
+    match chars.next() {
+        Some(c) if c.is_alphabetic() || c == '_' || c == '$' => {
+            chars.all(|c| c.is_alphanumeric() || c == '_' || c == '$')
+        }
+        _ => false,
+    }
+}
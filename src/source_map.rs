@@ -0,0 +1,98 @@
+use crate::cmd::{relative_path, Options};
+use crate::json_schema::json_string;
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// The comment `js::has_key` puts before the check of a property declared at byte `offset` of the input file.
+pub fn marker(offset: usize) -> String {
+    format!("/*@{offset}*/")
+}
+
+/// The offset in a comment made by `marker`.
+pub fn parse_marker(comment: &str) -> Option<usize> {
+    comment.strip_prefix("/*@")?.strip_suffix("*/")?.parse().ok()
+}
+
+/// Where a marker was taken out of the generated code, see `pretty::pretty_print_marked` and `pretty::strip_markers`:
+/// the line and UTF-16 column, and the byte offset in the input file it stands for.
+pub struct Mark {
+    pub line: usize,
+    pub column: usize,
+    pub offset: usize,
+}
+
+/// Returns the generated `code`, ending with a `sourceMappingURL` comment, and a version 3 source map that maps the
+/// position of each of `marks`, in order, to the line and column of its offset in `source`.
+pub fn source_map(code: &str, marks: &[Mark], source: &str, options: &Options) -> (String, String) {
+    let line_starts: Vec<usize> = std::iter::once(0)
+        .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+        .collect();
+    let mut mappings = String::new();
+    // Every field of a segment is relative to the one before, the column only within a line.
+    let mut previous = Segment::default();
+    let mut line = 0;
+    for mark in marks {
+        if mark.line > line {
+            mappings.push_str(&";".repeat(mark.line - line));
+            line = mark.line;
+            previous.column = 0;
+        } else if !mappings.is_empty() && !mappings.ends_with(';') {
+            mappings.push(',');
+        }
+        let source_line = line_starts.partition_point(|s| *s <= mark.offset) - 1;
+        let segment = Segment {
+            column: mark.column,
+            source_line,
+            source_column: source[line_starts[source_line]..mark.offset].encode_utf16().count(),
+        };
+        segment.encode(&previous, &mut mappings);
+        previous = segment;
+    }
+    let map_path = format!("{}.map", options.write_path);
+    let file = |path: &str| path.rsplit(['/', '\\']).next().unwrap_or(path).to_string();
+    let code = format!("{code}//# sourceMappingURL={}\n", file(&map_path));
+    let map = format!(
+        "{{\"version\":3,\"file\":{},\"sources\":[{}],\"names\":[],\"mappings\":{}}}\n",
+        json_string(&file(&options.write_path)),
+        json_string(&relative_path(&map_path, &options.read_path)),
+        json_string(&mappings)
+    );
+    (code, map)
+}
+
+#[derive(Default)]
+struct Segment {
+    column: usize,
+    source_line: usize,
+    source_column: usize,
+}
+
+impl Segment {
+    /// Appends the generated column, source index, source line and source column relative to `previous`.
+    fn encode(&self, previous: &Segment, mappings: &mut String) {
+        for delta in [
+            self.column as i64 - previous.column as i64,
+            0,
+            self.source_line as i64 - previous.source_line as i64,
+            self.source_column as i64 - previous.source_column as i64,
+        ] {
+            vlq(delta, mappings);
+        }
+    }
+}
+
+/// Base64 VLQ, with the sign in the lowest bit.
+fn vlq(value: i64, mappings: &mut String) {
+    let mut rest = if value < 0 { (-value << 1) | 1 } else { value << 1 };
+    loop {
+        let mut digit = rest & 31;
+        rest >>= 5;
+        if rest > 0 {
+            digit |= 32;
+        }
+        mappings.push(BASE64[digit as usize] as char);
+        if rest == 0 {
+            return;
+        }
+    }
+}
@@ -1,112 +1,1486 @@
-use crate::{lexer::tokenize, parsers::*};
-fn _test_(src: &str) -> Vec<Entry> {
-    let tokens = tokenize(src.to_string());
-    let mut interfaces = parse_interfaces(tokens);
-    for i in interfaces.iter_mut() {
-        for_each_value(i, parse_generics);
-    }
-    for i in interfaces.iter_mut() {
-        for_each_value(i, parse_arrays);
-    }
-    for i in interfaces.iter_mut() {
-        parse_and(i);
-    }
-    for i in interfaces.iter_mut() {
-        parse_or(i);
-    }
-    return interfaces;
-}
-
-#[cfg(test)]
-mod tests {
-    use crate::lexer::Type;
-
-    use super::*;
-
-    #[test]
-    fn test_parser() {
-        let raw = "
-        interface inter {
-            _1: number;
-        }
-        ";
-        let exp: Vec<Entry> = vec![Entry {
-            key: Key::Name("inter".to_string()),
-            value: vec![Value::Entry(Entry {
-                key: Key::Name("_1".to_string()),
-                value: vec![Value::Type(Type::Number)],
-            })],
-        }];
-        assert_eq!(_test_(raw), exp);
-
-        let raw = "
-        interface inter {
-            key_1: number;
-            key_2: string | { key_3: object };
-        }
-        ";
-        let exp: Vec<Entry> = vec![Entry {
-            key: Key::Name("inter".to_string()),
-            value: vec![
-                Value::Entry(Entry {
-                    key: Key::Name("key_1".to_string()),
-                    value: vec![Value::Type(Type::Number)],
-                }),
-                Value::Entry(Entry {
-                    key: Key::Name("key_2".to_string()),
-                    value: vec![Value::Entry(Entry {
-                        key: Key::Or,
-                        value: vec![
-                            Value::Type(Type::String),
-                            Value::Entry(Entry {
-                                key: Key::None,
-                                value: vec![Value::Entry(Entry {
-                                    key: Key::Name("key_3".to_string()),
-                                    value: vec![Value::Type(Type::Object)],
-                                })],
-                            }),
-                        ],
-                    })],
-                }),
-            ],
-        }];
-        assert_eq!(_test_(raw), exp);
-
-        let raw = "
-        interface inter {
-            _1: number[];
-        }
-        ";
-        let exp: Vec<Entry> = vec![Entry {
-            key: Key::Name("inter".to_string()),
-            value: vec![Value::Entry(Entry {
-                key: Key::Name("_1".to_string()),
-                value: vec![Value::Entry(Entry {
-                    key: Key::Generic(Generic::Array),
-                    value: vec![Value::Type(Type::Number)],
-                })],
-            })],
-        }];
-        assert_eq!(_test_(raw), exp);
-
-        let raw = "
-        interface inter {
-            _1: (number | string)[];
-        }
-        ";
-        let exp: Vec<Entry> = vec![Entry {
-            key: Key::Name("inter".to_string()),
-            value: vec![Value::Entry(Entry {
-                key: Key::Name("_1".to_string()),
-                value: vec![Value::Entry(Entry {
-                    key: Key::Generic(Generic::Array),
-                    value: vec![Value::Entry(Entry {
-                        key: Key::Or,
-                        value: vec![Value::Type(Type::Number), Value::Type(Type::String)],
-                    })],
-                })],
-            })],
-        }];
-        assert_eq!(_test_(raw), exp);
-    }
-}
+use crate::{lexer::tokenize, parsers::*};
+fn _test_(src: &str) -> Vec<Entry> {
+    let tokens = tokenize(src.to_string());
+    let mut interfaces = parse_interfaces(tokens);
+    for i in interfaces.iter_mut() {
+        for_each_value(i, parse_generics);
+    }
+    for i in interfaces.iter_mut() {
+        for_each_value(i, parse_arrays);
+    }
+    for i in interfaces.iter_mut() {
+        parse_and(i);
+    }
+    for i in interfaces.iter_mut() {
+        parse_or(i);
+    }
+    interfaces
+}
+
+fn _strip_(entries: Vec<Entry>) -> Vec<Value> {
+    strip_spans(&entries.into_iter().map(Value::Entry).collect::<Vec<Value>>())
+}
+
+fn _inline_(interfaces: &mut [Entry]) {
+    let interfaces_clone = interfaces.to_vec();
+    for i in interfaces.iter_mut() {
+        parse_custom_types(i, &interfaces_clone);
+    }
+}
+
+fn _object_(src: &str) -> Value {
+    Value::Entry(Entry {
+        key: Key::None,
+        value: _test_(src)[0].value.clone(),
+        doc: None,
+        params: Vec::new(),
+        span: Span::default(),
+    })
+}
+
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod tests {
+    use crate::lexer::Type;
+
+    use super::*;
+
+    #[test]
+    fn test_parser() {
+        let raw = "
+        interface inter {
+            _1: number;
+        }
+        ";
+        let exp: Vec<Entry> = vec![Entry {
+            key: Key::Name("inter".to_string()),
+            value: vec![Value::Entry(Entry {
+                key: Key::Name("_1".to_string()),
+                value: vec![Value::Type(Type::Number)],
+                doc: None,
+                params: Vec::new(),
+                span: Span::default(),
+            })],
+            doc: None,
+            params: Vec::new(),
+            span: Span::default(),
+        }];
+        assert_eq!(_strip_(_test_(raw)), _strip_(exp));
+
+        let raw = "
+        interface inter {
+            key_1: number;
+            key_2: string | { key_3: object };
+        }
+        ";
+        let exp: Vec<Entry> = vec![Entry {
+            key: Key::Name("inter".to_string()),
+            value: vec![
+                Value::Entry(Entry {
+                    key: Key::Name("key_1".to_string()),
+                    value: vec![Value::Type(Type::Number)],
+                    doc: None,
+                    params: Vec::new(),
+                    span: Span::default(),
+                }),
+                Value::Entry(Entry {
+                    key: Key::Name("key_2".to_string()),
+                    value: vec![Value::Entry(Entry {
+                        key: Key::Or,
+                        value: vec![
+                            Value::Type(Type::String),
+                            Value::Entry(Entry {
+                                key: Key::None,
+                                value: vec![Value::Entry(Entry {
+                                    key: Key::Name("key_3".to_string()),
+                                    value: vec![Value::Type(Type::Object)],
+                                    doc: None,
+                                    params: Vec::new(),
+                                    span: Span::default(),
+                                })],
+                                doc: None,
+                                params: Vec::new(),
+                                span: Span::default(),
+                            }),
+                        ],
+                        doc: None,
+                        params: Vec::new(),
+                        span: Span::default(),
+                    })],
+                    doc: None,
+                    params: Vec::new(),
+                    span: Span::default(),
+                }),
+            ],
+            doc: None,
+            params: Vec::new(),
+            span: Span::default(),
+        }];
+        assert_eq!(_strip_(_test_(raw)), _strip_(exp));
+
+        let raw = "
+        interface inter {
+            _1: number[];
+        }
+        ";
+        let exp: Vec<Entry> = vec![Entry {
+            key: Key::Name("inter".to_string()),
+            value: vec![Value::Entry(Entry {
+                key: Key::Name("_1".to_string()),
+                value: vec![Value::Entry(Entry {
+                    key: Key::Generic(Generic::Array),
+                    value: vec![Value::Type(Type::Number)],
+                    doc: None,
+                    params: Vec::new(),
+                    span: Span::default(),
+                })],
+                doc: None,
+                params: Vec::new(),
+                span: Span::default(),
+            })],
+            doc: None,
+            params: Vec::new(),
+            span: Span::default(),
+        }];
+        assert_eq!(_strip_(_test_(raw)), _strip_(exp));
+
+        let raw = "
+        interface inter {
+            _1: (number | string)[];
+        }
+        ";
+        let exp: Vec<Entry> = vec![Entry {
+            key: Key::Name("inter".to_string()),
+            value: vec![Value::Entry(Entry {
+                key: Key::Name("_1".to_string()),
+                value: vec![Value::Entry(Entry {
+                    key: Key::Generic(Generic::Array),
+                    value: vec![Value::Entry(Entry {
+                        key: Key::Or,
+                        value: vec![Value::Type(Type::Number), Value::Type(Type::String)],
+                        doc: None,
+                        params: Vec::new(),
+                        span: Span::default(),
+                    })],
+                    doc: None,
+                    params: Vec::new(),
+                    span: Span::default(),
+                })],
+                doc: None,
+                params: Vec::new(),
+                span: Span::default(),
+            })],
+            doc: None,
+            params: Vec::new(),
+            span: Span::default(),
+        }];
+        assert_eq!(_strip_(_test_(raw)), _strip_(exp));
+    }
+
+    #[test]
+    fn test_validator() {
+        use crate::{
+            cmd::{Extension, Options},
+            validate::*,
+        };
+
+        let options = Options {
+            extension: Extension::JS,
+            ..Default::default()
+        };
+
+        let addr: Vec<String> = vec!["o".into(), "foo".into(), "0".into(), "a b".into()];
+        assert_eq!(addr_to_path(&addr), "$.foo[0]['a b']");
+
+        let raw = "
+        interface inter {
+            key_1: string | number;
+            meta: unknown;
+            extra?: any;
+            /** @pattern ^\\d+$ */
+            code: string;
+        }
+        ";
+        let object = _object_(raw);
+        let js = to_js_validator(&object, vec!["o".to_string()], "e", 0, &options);
+        assert!(js.contains("e.push({path:\"$.key_1\",expected:\"string | number\",received:\"undefined\"})"));
+        assert!(js.contains("u0[1].push({path:\"$.key_1\",expected:\"number\",received:_received(o[\"key_1\"])})"));
+        assert!(js.contains("e.push({path:\"$.meta\",expected:\"unknown\",received:\"undefined\"})"));
+        assert!(!js.contains("else{}"));
+        assert!(js.contains("expected:\"match /^\\\\d+$/\""));
+        assert!(!js.contains("\"extra\""));
+
+        // Recursive references run the referenced validator, so the paths go down to the failing property.
+        let mut interfaces = _test_("interface Node { next: Node | null; }");
+        _inline_(&mut interfaces);
+        let object = Value::Entry(Entry {
+            key: Key::None,
+            value: interfaces[0].value.clone(),
+            doc: None,
+            params: Vec::new(),
+            span: Span::default(),
+        });
+        let js = to_js_validator(&object, vec!["o".to_string()], "e", 0, &options);
+        assert!(js.contains(
+            "{const r1=validateNode(o[\"next\"]);if(!r1.ok)for(const x of r1.errors)u0[0].push({...x,path:\"$.next\"+x.path.slice(1)})}"
+        ));
+    }
+
+    #[test]
+    fn test_assertion() {
+        use crate::{
+            cmd::{Extension, Import, Naming},
+            validate::assertion_dec,
+        };
+
+        let import = Import::parse("HttpError:./errors");
+        assert_eq!(import.name, "HttpError");
+        assert_eq!(import.module, Some("./errors".to_string()));
+        assert_eq!(Import::parse("TypeError").module, None);
+
+        let ts = assertion_dec("Foo", &[], "TypeError", Extension::TS, &Naming::default());
+        assert!(ts.starts_with("export function assertFoo(o: unknown): asserts o is Foo{const r=validateFoo(o);"));
+        assert!(ts.contains("throw new TypeError(\"Invalid Foo at \"+x.path"));
+    }
+
+    #[test]
+    fn test_reviver() {
+        use crate::validate::to_js_reviver;
+
+        let raw = "
+        interface inter {
+            key_1: bigint;
+            key_2: string | Date;
+            key_3: Date[];
+        }
+        ";
+        let object = _object_(raw);
+        let js = to_js_reviver(&object, "o", 0);
+        assert!(js.contains("o[\"key_1\"]=BigInt(o[\"key_1\"])"));
+        assert!(!js.contains("o[\"key_2\"]=new Date"));
+        assert!(js.contains("for(let i0=0;i0<o[\"key_3\"].length;i0++){"));
+
+        let raw = "
+        interface inter {
+            key_1: string;
+        }
+        ";
+        let object = _object_(raw);
+        assert_eq!(to_js_reviver(&object, "o", 0), "");
+    }
+
+    #[test]
+    fn test_jsdoc_constraints() {
+        use crate::jsdoc::{Constraint, Format};
+
+        let raw = "
+        interface inter {
+            /**
+             * Age in years.
+             * @minimum 0
+             * @integer
+             */
+            key_1: number;
+            // not a doc comment
+            /** @format email */
+            key_2?: string;
+            /** @pattern ^a/b$ */
+            key_3: string;
+        }
+        ";
+        let interfaces = _test_(raw);
+        let docs: Vec<Option<crate::jsdoc::Doc>> = interfaces[0]
+            .value
+            .iter()
+            .map(|i| match i {
+                Value::Entry(e) => e.doc.clone(),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(docs.len(), 3);
+        let doc = docs[0].clone().unwrap();
+        assert_eq!(doc.description, "Age in years.");
+        assert_eq!(
+            doc.constraints(),
+            vec![Constraint::Minimum("0".to_string()), Constraint::Integer]
+        );
+        assert_eq!(
+            docs[1].clone().unwrap().constraints(),
+            vec![Constraint::Format(Format::Email)]
+        );
+        assert_eq!(
+            docs[2].clone().unwrap().constraints(),
+            vec![Constraint::Pattern("/^a\\/b$/".to_string())]
+        );
+
+        let number = |raw: &str| std::panic::catch_unwind(|| crate::jsdoc::parse_doc(raw).constraints()).is_ok();
+        for valid in [
+            "@minimum -1",
+            "@maximum 0.5",
+            "@maximum .5",
+            "@minLength 2e3",
+            "@maxItems 1E+2",
+        ] {
+            assert!(number(valid), "{valid}");
+        }
+        for invalid in [
+            "@minimum inf",
+            "@maximum NaN",
+            "@minLength infinity",
+            "@minimum +1",
+            "@minimum 1e999",
+            "@minimum .",
+        ] {
+            assert!(!number(invalid), "{invalid}");
+        }
+
+        let pattern = |raw: &str| crate::jsdoc::parse_doc(raw).constraints();
+        assert_eq!(
+            pattern("@pattern ^a @b$\n@format hostname"),
+            vec![Constraint::Pattern("/^a @b$/".to_string())]
+        );
+        assert_eq!(
+            pattern("@pattern /^a+$/iu"),
+            vec![Constraint::Pattern("/^a+$/iu".to_string())]
+        );
+        assert_eq!(
+            pattern("@pattern /[/]/"),
+            vec![Constraint::Pattern("/[/]/".to_string())]
+        );
+        assert_eq!(
+            pattern("@pattern /a/.test(x)||true||/b/"),
+            vec![Constraint::Pattern("/\\/a\\/.test(x)||true||\\/b\\//".to_string())]
+        );
+        assert_eq!(
+            pattern("@pattern /a/gg"),
+            vec![Constraint::Pattern("/\\/a\\/gg/".to_string())]
+        );
+        assert_eq!(
+            pattern("@pattern a\\"),
+            vec![Constraint::Pattern("/a\\\\/".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_optimize() {
+        use crate::{
+            cmd::Options,
+            js::{js_tokens_to_string, to_js_token, JSToken},
+            optimize::optimize,
+        };
+
+        let options = Options::default();
+
+        let js = |raw: &str| {
+            let tokens = _test_(raw)[0]
+                .value
+                .iter()
+                .flat_map(|i| to_js_token(i.clone(), vec!["o".to_string()], &options))
+                .collect();
+            js_tokens_to_string(optimize(tokens))
+        };
+
+        let raw = "
+        interface inter {
+            key_1?: any;
+        }
+        ";
+        assert_eq!(js(raw), "true");
+
+        let raw = "
+        interface inter {
+            key_1: string | string | null;
+        }
+        ";
+        assert_eq!(
+            js(raw),
+            "Object.prototype.hasOwnProperty.call(o,\"key_1\")&&(typeof o[\"key_1\"]===\"string\"||o[\"key_1\"]===null)"
+        );
+
+        let raw = "
+        interface inter {
+            key_1: { k: 1 } | { k: string };
+        }
+        ";
+        assert_eq!(
+            js(raw),
+            "Object.prototype.hasOwnProperty.call(o,\"key_1\")&&typeof o[\"key_1\"]===\"object\"&&o[\"key_1\"]!=null&&!Array.isArray(o[\"key_1\"])&&Object.keys(o[\"key_1\"]).length===1&&Object.prototype.hasOwnProperty.call(o[\"key_1\"],\"k\")&&(o[\"key_1\"][\"k\"]===1||typeof o[\"key_1\"][\"k\"]===\"string\")"
+        );
+
+        let raw = "
+        interface inter {
+            key_1: string & number;
+        }
+        ";
+        assert_eq!(js(raw), "false");
+
+        // Literals are compared by value, and only ones known to differ contradict each other.
+        let both = |a: JSToken, b: JSToken| {
+            let addr = JSToken::Addr(vec!["o".to_string()]);
+            js_tokens_to_string(optimize(vec![
+                addr.clone(),
+                JSToken::EqEqEq,
+                a,
+                JSToken::And,
+                addr,
+                JSToken::EqEqEq,
+                b,
+            ]))
+        };
+        assert_eq!(
+            both(JSToken::Number("1".into()), JSToken::Number("1.0".into())),
+            "o===1&&o===1.0"
+        );
+        assert_eq!(
+            both(JSToken::Number("0".into()), JSToken::Number("-0".into())),
+            "o===0&&o===-0"
+        );
+        assert_eq!(both(JSToken::Number("1".into()), JSToken::Number("2".into())), "false");
+        assert_eq!(
+            both(JSToken::String("a".into()), JSToken::String("\\x61".into())),
+            "o===\"a\"&&o===\"\\x61\""
+        );
+        assert_eq!(both(JSToken::String("a".into()), JSToken::String("b".into())), "false");
+        assert_eq!(both(JSToken::String("1".into()), JSToken::Number("1".into())), "false");
+    }
+
+    #[test]
+    fn test_discriminated_union() {
+        use crate::{
+            cmd::{Extension, Options},
+            js::{js_tokens_to_string, to_js_token},
+            optimize::optimize,
+            validate::to_js_validator,
+        };
+
+        let options = Options {
+            extension: Extension::JS,
+            ..Default::default()
+        };
+        let raw = "
+        interface inter {
+            key_1: { kind: \"a\" | \"b\"; x: number } | { kind: \"c\"; y: string } | null;
+        }
+        ";
+        let tokens = _test_(raw)[0]
+            .value
+            .iter()
+            .flat_map(|i| to_js_token(i.clone(), vec!["o".to_string()], &options))
+            .collect();
+        let js = js_tokens_to_string(optimize(tokens));
+        assert!(js.contains(
+            "(()=>{switch(o[\"key_1\"][\"kind\"]){case \"a\":case \"b\":return(Object.keys(o[\"key_1\"]).length===2&&"
+        ));
+        assert!(js.contains(";case \"c\":return(Object.keys(o[\"key_1\"]).length===2&&"));
+        assert!(js.ends_with(";default:return false}})())"));
+        assert_eq!(js.matches("typeof o[\"key_1\"]===\"object\"").count(), 1);
+
+        let object = _object_(raw);
+        let js = to_js_validator(&object, vec!["o".to_string()], "e", 0, &options);
+        assert!(js.contains(
+            "switch(o[\"key_1\"][\"kind\"]){case \"a\":case \"b\":{if(Object.keys(o[\"key_1\"]).length!==2)"
+        ));
+        assert_eq!(js.matches("typeof o[\"key_1\"]!==\"object\"").count(), 1);
+        assert!(!js.contains("o[\"key_1\"][\"kind\"]===\"c\""));
+        assert!(js.contains("default:u0[1].push({path:\"$.key_1.kind\",expected:\"\\\"a\\\" | \\\"b\\\" | \\\"c\\\"\""));
+
+        let pretty = crate::pretty::pretty_print(&js, &options.style);
+        assert!(pretty.contains(
+            "            else switch (o[\"key_1\"][\"kind\"]) {
+                case \"a\":
+                case \"b\":
+                    {
+                        if (Object.keys(o[\"key_1\"]).length !== 2)
+"
+        ));
+        assert!(pretty.contains(
+            "                    }
+                    break;
+                case \"c\":
+                    {
+"
+        ));
+        assert!(pretty.contains("                default:\n                    u0[1].push({\n"));
+    }
+
+    #[test]
+    fn test_own_keys() {
+        use crate::{
+            cmd::Options,
+            js::{js_tokens_to_string, to_js_token},
+        };
+
+        let raw = "
+        interface inter {
+            key_1: string;
+            constructor?: string;
+        }
+        ";
+        let js = |options: &Options| {
+            let tokens = _test_(raw)[0]
+                .value
+                .iter()
+                .flat_map(|i| to_js_token(i.clone(), vec!["o".to_string()], options))
+                .collect();
+            js_tokens_to_string(tokens)
+        };
+        let own = js(&Options::default());
+        assert!(own.contains("Object.prototype.hasOwnProperty.call(o,\"key_1\")&&"));
+        assert!(own.contains("Object.prototype.hasOwnProperty.call(o,\"constructor\")===false||"));
+
+        let inherited = js(&Options {
+            inherited_keys: true,
+            ..Default::default()
+        });
+        assert!(inherited.contains("\"key_1\"in o&&"));
+        assert!(inherited.contains("Object.prototype.hasOwnProperty.call(o,\"constructor\")===false||"));
+    }
+
+    #[test]
+    fn test_object_shape() {
+        use crate::{
+            cmd::Options,
+            js::{js_tokens_to_string, return_body, to_js_token},
+        };
+
+        let raw = "
+        interface inter {
+            key_1: { a: object };
+        }
+        ";
+        let js = |options: &Options| {
+            let entries = _test_(raw);
+            let tokens = entries[0]
+                .value
+                .iter()
+                .flat_map(|i| to_js_token(i.clone(), vec!["o".to_string()], options))
+                .collect();
+            js_tokens_to_string(return_body(entries[0].value.len(), tokens, options))
+        };
+        let js_default = js(&Options::default());
+        assert!(js_default.starts_with("o!=null&&typeof o===\"object\"&&!Array.isArray(o)&&Object.keys(o).length===1"));
+        assert!(js_default.contains("!Array.isArray(o[\"key_1\"])&&"));
+        assert!(js_default.contains("typeof o[\"key_1\"][\"a\"]===\"object\"&&o[\"key_1\"][\"a\"]!=null"));
+        assert!(!js_default.contains("getPrototypeOf"));
+
+        let js_plain = js(&Options {
+            plain_objects: true,
+            ..Default::default()
+        });
+        assert!(js_plain.contains(
+            "&&(Object.getPrototypeOf(o[\"key_1\"])===Object.prototype||Object.getPrototypeOf(o[\"key_1\"])===null)&&"
+        ));
+    }
+
+    #[test]
+    fn test_generic_interface() {
+        use crate::{
+            cmd::{Extension, Naming, Options},
+            js::{function_dec, js_tokens_to_string, to_js_token},
+        };
+
+        let raw = "
+        interface Page<T, M extends object = object> {
+            items: T[];
+            meta: M;
+        }
+        interface Users {
+            page: Page<string, { n: number }>;
+        }
+        ";
+        let mut interfaces = _test_(raw);
+        assert_eq!(interfaces[0].params, vec!["T".to_string(), "M".to_string()]);
+        assert_eq!(
+            strip_spans(&interfaces[0].value[1..2]),
+            vec![Value::Entry(Entry {
+                key: Key::Name("meta".to_string()),
+                value: vec![Value::Type(Type::Param("M".to_string()))],
+                doc: None,
+                params: Vec::new(),
+                span: Span::default(),
+            })]
+        );
+
+        let options = Options::default();
+        let body = interfaces[0]
+            .value
+            .iter()
+            .flat_map(|i| to_js_token(i.clone(), vec!["o".to_string()], &options))
+            .collect();
+        let dec = function_dec(
+            "Page".to_string(),
+            &interfaces[0].params,
+            js_tokens_to_string(body),
+            Extension::TS,
+            &Naming::default(),
+        );
+        assert!(dec.starts_with(
+            "export function isPage<T,M>(o: unknown,isT: (x: unknown) => x is T,isM: (x: unknown) => x is M): o is Page<T,M>{"
+        ));
+        assert!(dec.contains("isT(o[\"items\"][\"0\"])"));
+        assert!(dec.contains("isM(o[\"meta\"])"));
+
+        _inline_(&mut interfaces);
+        assert_eq!(
+            value_to_ts_string(&interfaces[1].value[0]),
+            "{ items: string[]; meta: { n: number } }"
+        );
+    }
+
+    #[test]
+    fn test_recursive_generic_interface() {
+        use crate::{
+            cmd::Options,
+            js::{js_tokens_to_string, to_js_token},
+        };
+
+        let raw = "
+        interface Tree<T> {
+            value: T;
+            children: Tree<T>[];
+        }
+        interface Box<T = string> {
+            item: T;
+        }
+        interface Forest {
+            trees: Tree<number>;
+            boxed: Box;
+        }
+        ";
+        let mut interfaces = _test_(raw);
+        _inline_(&mut interfaces);
+        let options = Options::default();
+        let guard = |i: &Entry| {
+            js_tokens_to_string(
+                i.value
+                    .iter()
+                    .flat_map(|i| to_js_token(i.clone(), vec!["o".to_string()], &options))
+                    .collect(),
+            )
+        };
+        assert!(guard(&interfaces[0]).contains("isTree(o[\"children\"][\"0\"],isT)"));
+        let forest = guard(&interfaces[2]);
+        assert!(forest
+            .contains("isTree(o[\"trees\"][\"children\"][\"0\"],(x: unknown): x is number=>typeof x===\"number\")"));
+        assert!(forest.contains("typeof o[\"boxed\"][\"item\"]===\"string\""));
+        assert_eq!(
+            value_to_ts_string(&interfaces[2].value[0]),
+            "{ value: number; children: Tree<number>[] }"
+        );
+    }
+
+    #[test]
+    fn test_brand() {
+        use crate::{
+            cmd::{Import, Options},
+            js::{js_tokens_to_string, to_js_token},
+            lexer::find_interfaces,
+        };
+
+        let raw = "
+        export type UserId = string & { readonly __brand: \"UserId\" }
+        const type = 1;
+        interface inter {
+            key_1: UserId;
+            key_2: number & {};
+        }
+        "
+        .to_string();
+        let mut interfaces = _test_(&find_interfaces(raw));
+        assert_eq!(interfaces[0].key, Key::Alias("UserId".to_string()));
+        _inline_(&mut interfaces);
+        parse_and(&mut interfaces[1]);
+
+        let js = |options: &Options| {
+            let tokens = interfaces[1]
+                .value
+                .iter()
+                .flat_map(|i| to_js_token(i.clone(), vec!["o".to_string()], options))
+                .collect();
+            js_tokens_to_string(tokens)
+        };
+        let options = Options {
+            brands: vec![("UserId".to_string(), Import::parse("isUserId:./brands"))],
+            ..Default::default()
+        };
+        assert_eq!(
+            js(&Options::default()),
+            "&&Object.prototype.hasOwnProperty.call(o,\"key_1\")&&(typeof o[\"key_1\"]===\"string\")&&Object.prototype.hasOwnProperty.call(o,\"key_2\")&&(typeof o[\"key_2\"]===\"number\")"
+        );
+        assert!(js(&options).contains("(typeof o[\"key_1\"]===\"string\"&&isUserId(o[\"key_1\"]))"));
+    }
+
+    #[test]
+    fn test_custom_guards() {
+        use crate::{
+            cmd::{parse_guards, Import, Options},
+            js::{imports_dec, js_tokens_to_string, to_js_token},
+        };
+
+        let guards = parse_guards(
+            "
+            # library types
+            Decimal = isDecimal from \"./guards\"
+            Temporal.Instant = isInstant from './guards';
+            ObjectId = ObjectId.isValid
+            ",
+        );
+        assert_eq!(
+            guards,
+            vec![
+                ("Decimal".to_string(), Import::parse("isDecimal:./guards")),
+                ("Temporal.Instant".to_string(), Import::parse("isInstant:./guards")),
+                ("ObjectId".to_string(), Import::parse("ObjectId.isValid")),
+            ]
+        );
+        assert_eq!(
+            imports_dec(guards.iter().map(|(_, i)| i).collect()),
+            "import { isDecimal, isInstant } from \"./guards\";\n"
+        );
+
+        let raw = "
+        interface inter {
+            key_1: Temporal.Instant | null;
+        }
+        ";
+        let options = Options {
+            guards,
+            ..Default::default()
+        };
+        let tokens = _test_(raw)[0]
+            .value
+            .iter()
+            .flat_map(|i| to_js_token(i.clone(), vec!["o".to_string()], &options))
+            .collect();
+        assert!(js_tokens_to_string(tokens).contains("(isInstant(o[\"key_1\"])||o[\"key_1\"]===null)"));
+    }
+
+    #[test]
+    fn test_merge_intersections() {
+        let raw = "
+        interface inter {
+            key_1: { a: string; s?: number; n: { x: number } } & { b: 1; s: number; n: { y: string } };
+            key_2: { k: \"x\" } & { k: number };
+            key_3: string & number;
+            key_4: { u: { z: 1 } | null } & { u: { z: 1 } | null };
+        }
+        ";
+        let mut interfaces = _test_(raw);
+        merge_intersections(&mut interfaces[0]);
+        let ts: Vec<String> = interfaces[0].value.iter().map(value_to_ts_string).collect();
+        assert_eq!(
+            ts,
+            vec![
+                "{ a: string; s: number; n: { x: number; y: string }; b: 1 }",
+                "{ k: \"x\" & number }",
+                "string & number",
+                "{ u: { z: 1 } | null }",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_zod() {
+        use crate::{
+            cmd::{Extension, Options},
+            zod::interfaces_to_zod,
+        };
+
+        let raw = "
+        interface Tree {
+            value: Leaf;
+            children?: Tree[];
+        }
+        type Leaf = [number, \"a\" | \"b\"];
+        interface Page<T> {
+            items: T[];
+        }
+        interface Note {
+            /**
+             * First line
+             * C:\\temp and \"quoted\"
+             */
+            text: string;
+        }
+        ";
+        let mut interfaces = _test_(raw);
+        _inline_(&mut interfaces);
+        interfaces.retain(|i| !matches!(i.key, Key::Alias(_)));
+        for i in interfaces.iter_mut() {
+            parse_tuples(i);
+        }
+
+        let options = Options {
+            extension: Extension::TS,
+            ..Default::default()
+        };
+        let zod = interfaces_to_zod(&interfaces, &options);
+        assert!(zod.starts_with("import { z } from \"zod\";\n"));
+        assert!(zod.contains(
+            "export type Tree = { value: [number, \"a\" | \"b\"]; children?: Tree[] };\nexport const TreeSchema: z.ZodType<Tree> = z.lazy(() => z.object({ value: z.tuple([z.number(), z.union([z.literal(\"a\"), z.literal(\"b\")])]), children: z.array(z.lazy(() => TreeSchema)).optional() }).strict());"
+        ));
+        assert!(zod.contains(
+            "export const PageSchema = <T extends z.ZodTypeAny>(T: T) => z.object({ items: z.array(T) }).strict();"
+        ));
+        assert!(zod.contains("text: z.string().describe(\"First line\\nC:\\\\temp and \\\"quoted\\\"\")"));
+    }
+
+    #[test]
+    fn test_json_schema() {
+        use crate::json_schema::{definitions, Json};
+
+        let raw = "
+        interface Point {
+            /** Distance from the left
+             * @minimum 0
+             * @integer */
+            x: number;
+            label?: \"a\" | \"b\" | undefined;
+            next: Point | null;
+            pair: [string, boolean];
+        }
+        ";
+        let mut interfaces = _test_(raw);
+        _inline_(&mut interfaces);
+        for i in interfaces.iter_mut() {
+            parse_tuples(i);
+        }
+
+        let (name, schema) = &definitions(&interfaces, "#/$defs/")[0];
+        assert_eq!(name, "Point");
+        assert_eq!(
+            schema.to_pretty_string(0).replace([' ', '\n'], ""),
+            r##"{
+                "type": "object",
+                "properties": {
+                    "x": { "type": "integer", "description": "Distancefromtheleft", "minimum": 0 },
+                    "label": { "enum": ["a", "b"] },
+                    "next": { "anyOf": [{ "$ref": "#/$defs/Point" }, { "type": "null" }] },
+                    "pair": {
+                        "type": "array",
+                        "prefixItems": [{ "type": "string" }, { "type": "boolean" }],
+                        "items": false,
+                        "minItems": 2
+                    }
+                },
+                "required": ["x", "next", "pair"],
+                "additionalProperties": false
+            }"##
+            .replace([' ', '\n'], "")
+        );
+        assert_eq!(Json::String("a\"\n".to_string()).to_pretty_string(0), r#""a\"\n""#);
+    }
+
+    #[test]
+    fn test_rust() {
+        use crate::{
+            case::pascal_case,
+            rust::{interfaces_to_rust, snake_case},
+        };
+
+        assert_eq!(snake_case("firstName"), "first_name");
+        assert_eq!(snake_case("userID"), "user_id");
+        assert_eq!(snake_case("HTTPStatus"), "http_status");
+        assert_eq!(snake_case("type"), "r#type");
+        assert_eq!(snake_case("self"), "self_");
+        for keyword in [
+            "final", "macro", "override", "priv", "typeof", "unsized", "virtual", "do", "become", "gen",
+        ] {
+            assert_eq!(snake_case(keyword), format!("r#{keyword}"));
+        }
+        assert_eq!(pascal_case("in-progress"), "InProgress");
+        assert_eq!(pascal_case("2fa"), "V2fa");
+
+        let raw = "
+        interface Node {
+            tag?: \"a\" | \"b\";
+            shape: { kind: \"circle\"; r: number } | { kind: \"square\"; side: number };
+            next: Node | null;
+            pair: [string, any];
+        }
+        ";
+        let mut interfaces = _test_(raw);
+        _inline_(&mut interfaces);
+        for i in interfaces.iter_mut() {
+            parse_tuples(i);
+        }
+        let rust = interfaces_to_rust(&interfaces);
+        assert!(rust.contains(
+            "pub enum NodeTag {\n    #[serde(rename = \"a\")]\n    A,\n    #[serde(rename = \"b\")]\n    B,\n}"
+        ));
+        assert!(rust.contains(
+            "#[serde(tag = \"kind\", deny_unknown_fields)]\npub enum NodeShape {\n    #[serde(rename = \"circle\")]\n    Circle {\n        r: f64,\n    },\n    #[serde(rename = \"square\")]\n    Square {\n        side: f64,\n    },\n}"
+        ));
+        assert!(rust.contains(
+            "pub struct Node {\n    #[serde(default, skip_serializing_if = \"Option::is_none\")]\n    pub tag: Option<NodeTag>,\n    pub shape: NodeShape,\n    pub next: Option<Box<Node>>,\n    pub pair: (String, serde_json::Value),\n}"
+        ));
+
+        let rust = interfaces_to_rust(&_test_(
+            "interface Names { fooBar: string; foo_bar: number; type: boolean; Type: null; }",
+        ));
+        assert!(rust.contains(
+            "    #[serde(rename = \"fooBar\")]\n    pub foo_bar: String,\n    #[serde(rename = \"foo_bar\")]\n    pub foo_bar_2: f64,\n"
+        ));
+        assert!(rust.contains("    pub r#type: bool,\n    #[serde(rename = \"Type\")]\n    pub type_2: (),\n"));
+    }
+
+    #[test]
+    fn test_openapi() {
+        use crate::json_schema::interfaces_to_openapi;
+
+        let raw = "
+        interface Item {
+            name: string;
+            tags: (\"new\" | \"yes\")[];
+            parent: Item | null;
+        }
+        ";
+        let mut interfaces = _test_(raw);
+        _inline_(&mut interfaces);
+        assert_eq!(
+            interfaces_to_openapi(&interfaces, true),
+            "components:
+  schemas:
+    Item:
+      type: object
+      properties:
+        name:
+          type: string
+        tags:
+          type: array
+          items:
+            enum:
+              - new
+              - \"yes\"
+        parent:
+          anyOf:
+            - $ref: \"#/components/schemas/Item\"
+            - type: \"null\"
+      required:
+        - name
+        - tags
+        - parent
+      additionalProperties: false
+"
+        );
+        assert!(interfaces_to_openapi(&interfaces, false)
+            .starts_with("{\n  \"components\": {\n    \"schemas\": {\n      \"Item\": {\n"));
+    }
+
+    #[test]
+    fn test_python() {
+        use crate::python::{identifier, interfaces_to_python};
+
+        assert_eq!(identifier("firstName"), "firstName");
+        assert_eq!(identifier("first-name"), "first_name");
+        assert_eq!(identifier("class"), "class_");
+
+        let raw = "
+        interface Event {
+            /** When it happened */
+            at: string;
+            level?: \"info\" | \"warn\" | 3;
+            next: Event | null;
+            \"x-id\": number;
+        }
+        ";
+        let mut interfaces = _test_(raw);
+        _inline_(&mut interfaces);
+        assert!(interfaces_to_python(&interfaces, false).ends_with(
+            "Event = TypedDict(
+    \"Event\",
+    {
+        \"at\": str,
+        \"level\": NotRequired[Literal[\"info\", \"warn\", 3]],
+        \"next\": 'Event | None',
+        \"x-id\": float,
+    },
+)
+"
+        ));
+        assert!(interfaces_to_python(&interfaces, true).ends_with(
+            "class Event(BaseModel):
+    model_config = ConfigDict(extra=\"forbid\")
+
+    at: str = Field(description=\"When it happened\")
+    level: Literal[\"info\", \"warn\", 3] | None = None
+    next: Event | None
+    x_id: float = Field(alias=\"x-id\")
+"
+        ));
+
+        let raw = "
+        /** Written as \"C:\\\\temp\" */
+        interface Path {
+            at: string;
+        }
+        ";
+        let interfaces = _test_(raw);
+        assert!(interfaces_to_python(&interfaces, false).contains(
+            "class Path(TypedDict):
+    \"\"\"Written as \\\"C:\\\\\\\\temp\\\"\"\"\"
+"
+        ));
+    }
+
+    #[test]
+    fn test_module_format() {
+        use crate::{
+            cmd::{Import, Module},
+            js::module_dec,
+        };
+
+        let error = Import::parse("HttpError:./errors");
+        let body = "function _received(x){return(typeof x)}\n\nexport function isFoo(o){return(true)}\nexport function isBar(o,isT){return(isT(o))}".to_string();
+        assert_eq!(
+            module_dec(vec![&error], body.clone(), &Module::Esm),
+            format!("import {{ HttpError }} from \"./errors\";\n{body}")
+        );
+        assert_eq!(
+            module_dec(vec![&error], body.clone(), &Module::CommonJs),
+            "\"use strict\";\nconst { HttpError } = require(\"./errors\");\nfunction _received(x){return(typeof x)}\n\nfunction isFoo(o){return(true)}\nfunction isBar(o,isT){return(isT(o))}\nexports.isFoo=isFoo;\nexports.isBar=isBar;\n"
+        );
+        assert_eq!(
+            module_dec(Vec::new(), body, &Module::Global("Guards".to_string())),
+            "(function(ns){\"use strict\";\nfunction _received(x){return(typeof x)}\n\nfunction isFoo(o){return(true)}\nfunction isBar(o,isT){return(isT(o))}\nns.isFoo=isFoo;\nns.isBar=isBar;\n})(globalThis[\"Guards\"]=globalThis[\"Guards\"]||{});\n"
+        );
+    }
+
+    #[test]
+    fn test_declarations() {
+        use crate::{
+            cmd::{relative_module, Extension, Naming},
+            js::function_dec,
+            validate::{parse_helpers_dec, validator_dec},
+        };
+
+        let params = vec!["T".to_string()];
+        assert_eq!(
+            function_dec(
+                "Page".to_string(),
+                &params,
+                "true".to_string(),
+                Extension::DTS,
+                &Naming::default()
+            ),
+            "export declare function isPage<T>(o: unknown,isT: (x: unknown) => x is T): o is Page<T>;"
+        );
+        assert_eq!(
+            validator_dec("Foo", &[], String::new(), Extension::DTS, &Naming::default()),
+            "export declare function validateFoo(input: unknown): ValidationResult<Foo>;"
+        );
+        assert_eq!(
+            parse_helpers_dec(
+                "Foo",
+                &[],
+                "TypeError",
+                Some("o.a=1;".to_string()),
+                Extension::DTS,
+                &Naming::default()
+            ),
+            "export declare function parseFoo(json: string): Foo;\nexport declare function safeParseFoo(json: string): ValidationResult<Foo>;"
+        );
+
+        assert_eq!(relative_module("guards.js", "types.ts"), "./types");
+        assert_eq!(relative_module("out/guards.d.ts", "src/types.d.ts"), "../src/types");
+        assert_eq!(relative_module("./guards.js", "src/api/types.ts"), "./src/api/types");
+    }
+
+    #[test]
+    fn test_pretty_print() {
+        use crate::{cmd::Style, pretty::pretty_print};
+
+        let js = "export function isFoo(o: unknown): o is Foo{return(o!=null&&typeof o===\"object\"&&(o[\"a\"]===\"it's\"||o[\"a\"]===-1))}\nfunction f(x){if(!(x>0))e.push({path:\"$\",expected:\"number\"});else{const b=x.every(y=>y.ok)?[x]:[];}return b}";
+        assert_eq!(
+            pretty_print(js, &Style::default()),
+            "export function isFoo(o: unknown): o is Foo {
+    return o != null && typeof o === \"object\" && (o[\"a\"] === \"it's\" || o[\"a\"] === -1);
+}
+function f(x) {
+    if (!(x > 0)) e.push({ path: \"$\", expected: \"number\" });
+    else {
+        const b = x.every(y => y.ok) ? [x] : [];
+    }
+    return b;
+}
+"
+        );
+        let style = Style {
+            print_width: 40,
+            use_tabs: true,
+            single_quote: true,
+            semi: false,
+            ..Style::default()
+        };
+        assert_eq!(
+            pretty_print(js, &style),
+            "export function isFoo(o: unknown): o is Foo {
+\treturn (
+\t\to != null &&
+\t\ttypeof o === 'object' &&
+\t\t(o['a'] === \"it's\" ||
+\t\t\to['a'] === -1)
+\t)
+}
+function f(x) {
+\tif (!(x > 0))
+\t\te.push({
+\t\t\tpath: '$',
+\t\t\texpected: 'number'
+\t\t})
+\telse {
+\t\tconst b = x.every(y => y.ok)
+\t\t\t? [x]
+\t\t\t: []
+\t}
+\treturn b
+}
+"
+        );
+
+        // An arrow function body breaks the expression around it.
+        assert_eq!(
+            pretty_print(
+                "function g(o){return(o!=null&&(()=>{switch(o.k){case 1:return true;default:return false}})())}",
+                &Style::default()
+            ),
+            "function g(o) {
+    return (
+        o != null &&
+        (() => {
+            switch (o.k) {
+                case 1:
+                    return true;
+                default:
+                    return false;
+            }
+        })()
+    );
+}
+"
+        );
+    }
+
+    #[test]
+    fn test_source_map() {
+        use crate::{
+            cmd::{Options, Style},
+            js::interfaces_to_js_marked,
+            lexer::find_interfaces,
+            pretty::{pretty_print_marked, strip_markers},
+            source_map::source_map,
+        };
+
+        let source = "interface Foo {\n  a: string;\n  b: number;\n}\n";
+        let options = Options {
+            read_path: "foo.ts".to_string(),
+            write_path: "out/foo.js".to_string(),
+            ..Default::default()
+        };
+        let (code, marks) = strip_markers("function isFoo(o){return(/*@18*/\"a\"in o&&/*@31*/\"b\"in o)}\n");
+        let (code, map) = source_map(&code, &marks, source, &options);
+        assert_eq!(
+            code,
+            "function isFoo(o){return(\"a\"in o&&\"b\"in o)}\n//# sourceMappingURL=foo.js.map\n"
+        );
+        assert_eq!(
+            map,
+            "{\"version\":3,\"file\":\"foo.js\",\"sources\":[\"../foo.ts\"],\"names\":[],\"mappings\":\"yBACE,SACA\"}\n"
+        );
+        // The markers take no width when pretty printing, so they don't move the line breaks.
+        let (code, marks) = pretty_print_marked("function f(o){return/*@18*/o.a&&/*@31*/o.b}", &Style::default());
+        assert_eq!(code, "function f(o) {\n    return o.a && o.b;\n}\n");
+        assert_eq!(
+            marks.iter().map(|m| (m.line, m.column, m.offset)).collect::<Vec<_>>(),
+            vec![(1, 11, 18), (1, 18, 31)]
+        );
+
+        // A quote in a regular expression doesn't hide the markers after it.
+        let source = "interface Foo { /** @pattern ^a\"b$ */ x: string; y: number; }";
+        let options = Options {
+            source_map: true,
+            ..options
+        };
+        for minify in [true, false] {
+            let options = Options {
+                style: Style {
+                    minify,
+                    ..Default::default()
+                },
+                ..options.clone()
+            };
+            let interfaces = parse_interfaces(tokenize(find_interfaces(source.to_string())));
+            let (code, marks) = interfaces_to_js_marked(interfaces, source, &options);
+            assert!(!code.contains("/*@"), "{code}");
+            assert_eq!(marks.iter().map(|m| m.offset).collect::<Vec<_>>(), vec![38, 49]);
+        }
+    }
+
+    #[test]
+    fn test_guard_docs() {
+        use crate::{
+            cmd::{Extension, Options, Style},
+            js::interfaces_to_js_string,
+            lexer::find_interfaces,
+        };
+
+        let source = "const a = 1;\n/**\n * A user\n * @deprecated use Person\n */\nexport interface User {\n    id: string;\n}\n";
+        let options = Options {
+            read_path: "user.ts".to_string(),
+            write_path: "user.guard.js".to_string(),
+            extension: Extension::JS,
+            style: Style {
+                minify: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let interfaces = parse_interfaces(tokenize(find_interfaces(source.to_string())));
+        assert_eq!(
+            interfaces_to_js_string(interfaces, source, &options),
+            "/**
+ * @generated by ts-runtime-typechecker from user.ts, do not edit.
+ */
+
+/**
+ * A user
+ *
+ * Type guard for `User`, declared at user.ts:6.
+ * @deprecated use Person
+ */
+export function isUser(o){return(o!=null&&typeof o===\"object\"&&!Array.isArray(o)&&Object.keys(o).length===1&&Object.prototype.hasOwnProperty.call(o,\"id\")&&typeof o[\"id\"]===\"string\")}
+"
+        );
+    }
+
+    #[test]
+    fn test_naming() {
+        use crate::{
+            cmd::{Extension, Naming, Options},
+            js::{function_dec, js_tokens_to_string, to_js_token},
+            validate::parse_helpers_dec,
+        };
+
+        let naming = Naming {
+            guard: "{name}Guard".to_string(),
+            validator: "check_{snake_name}".to_string(),
+            assertion: "assert{Name}".to_string(),
+            parser: "{name}FromJson".to_string(),
+            safe_parser: "try{Name}FromJson".to_string(),
+        };
+        assert_eq!(naming.guard("HTTPRequest"), "httpRequestGuard");
+        assert_eq!(naming.validator("HTTPRequest"), "check_http_request");
+        assert_eq!(naming.assertion("user_id"), "assertuser_id");
+        assert_eq!(naming.guard("user_id"), "user_idGuard");
+
+        let dec = function_dec("Foo".to_string(), &[], "true".to_string(), Extension::JS, &naming);
+        assert_eq!(dec, "export function fooGuard(o){return(true)}");
+        let dec = function_dec(
+            "Page".to_string(),
+            &["T".to_string()],
+            "true".to_string(),
+            Extension::JS,
+            &naming,
+        );
+        assert_eq!(dec, "export function pageGuard(o,tGuard){return(true)}");
+        let helpers = parse_helpers_dec("Page", &["T".to_string()], "TypeError", None, Extension::JS, &naming);
+        assert!(helpers
+            .starts_with("export function pageFromJson(json,tGuard){const r=check_page(JSON.parse(json),tGuard);"));
+        assert!(helpers.contains("\nexport function tryPageFromJson(json,tGuard){"));
+        let raw = "
+        interface Foo {
+            key_1: Foo | null;
+        }
+        ";
+        let mut interfaces = _test_(raw);
+        _inline_(&mut interfaces);
+        let options = Options {
+            naming,
+            ..Default::default()
+        };
+        let tokens = interfaces[0]
+            .value
+            .iter()
+            .flat_map(|i| to_js_token(i.clone(), vec!["o".to_string()], &options))
+            .collect();
+        assert!(js_tokens_to_string(tokens).contains("fooGuard(o[\"key_1\"])"));
+        let tokens = to_js_token(
+            Value::Type(Type::Param("T".to_string())),
+            vec!["o".to_string()],
+            &options,
+        );
+        assert_eq!(js_tokens_to_string(tokens), "tGuard(o)");
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Two generated functions would be named check_user_id: the guard of UserId and the guard of user_id"
+    )]
+    fn test_name_collision() {
+        use crate::{
+            cmd::{Naming, Options},
+            js::interfaces_to_js_string,
+        };
+
+        let options = Options {
+            naming: Naming {
+                guard: "check_{snake_name}".to_string(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        interfaces_to_js_string(
+            _test_("interface UserId { a: string }\ninterface user_id { b: number }"),
+            "",
+            &options,
+        );
+    }
+
+    #[test]
+    fn test_out_dir() {
+        use crate::{
+            cmd::{Extension, Import, Options, Style},
+            js::interfaces_to_js_files,
+        };
+
+        let raw = "
+        interface Tree { children: Tree[]; owner: User }
+        interface User { best?: Tree; mail: Email }
+        interface Flags { isEmailVerified: boolean }
+        ";
+        let mut interfaces = _test_(raw);
+        _inline_(&mut interfaces);
+        let options = Options {
+            read_path: "types.ts".to_string(),
+            write_path: "out".to_string(),
+            extension: Extension::TS,
+            out_dir: true,
+            validators: true,
+            guards: vec![("Email".to_string(), Import::parse("isEmail:./email"))],
+            style: Style {
+                minify: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let files = interfaces_to_js_files(interfaces, raw, &options);
+        let paths: Vec<&str> = files.iter().map(|(path, _)| path.as_str()).collect();
+        assert_eq!(
+            paths,
+            [
+                "out/isTree.ts",
+                "out/isUser.ts",
+                "out/isFlags.ts",
+                "out/validation.ts",
+                "out/index.ts"
+            ]
+        );
+        assert!(files[1].1.contains(
+            "import type { User } from \"../types\";\nimport { _received, type ValidationError, type ValidationResult } from \"./validation\";\nimport { isTree, validateTree } from \"./isTree\";\nimport { isEmail } from \"./email\";\n"
+        ));
+        assert!(!files[0].1.contains("import { isTree }"));
+        assert!(files[2].1.contains("isEmailVerified"));
+        assert!(!files[2].1.contains("import { isEmail }"));
+        assert!(files[3].1.contains("export function _received(x: unknown): string{"));
+        assert!(files[4].1.ends_with(
+            "export * from \"./isTree\";\nexport * from \"./isUser\";\nexport * from \"./isFlags\";\nexport type { ValidationError, ValidationResult } from \"./validation\";\n"
+        ));
+    }
+
+    #[test]
+    fn test_emit_tests() {
+        use crate::{
+            cmd::{Extension, Options},
+            samples::{interfaces_to_tests, tests_path},
+        };
+
+        let raw = "
+        interface Foo {
+            kind: \"a\" | \"b\";
+            pos: [number, string];
+            tags?: boolean[];
+        }
+        interface Node {
+            next: Node;
+        }
+        interface Account {
+            /** @pattern ^[a-z]+$ */
+            handle: string;
+            /**
+             * @pattern ^\\d{3}$
+             * @example \"123\"
+             */
+            code: string;
+            /** @minItems 1e300 */
+            ids?: number[];
+        }
+        ";
+        let mut interfaces = _test_(raw);
+        parse_tuples(&mut interfaces[0]);
+        _inline_(&mut interfaces);
+        let mut options = Options {
+            read_path: "types.ts".to_string(),
+            write_path: "guards.js".to_string(),
+            extension: Extension::JS,
+            ..Default::default()
+        };
+        options.style.minify = true;
+        assert_eq!(tests_path(&options), "guards.test.ts");
+        let tests = interfaces_to_tests(&interfaces, &options);
+        assert!(tests.contains("import { isFoo, isAccount } from \"./guards.js\";"));
+        assert!(tests.contains(
+            "test(\"isFoo accepts a valid Foo\",()=>assert.equal(isFoo({kind:\"a\",pos:[1,\"text\"],tags:[true]}),true));"
+        ));
+        for (title, value) in [
+            ("a missing required key at $.kind", "{pos:[1,\"text\"],tags:[true]}"),
+            (
+                "a wrong literal at $.kind",
+                "{kind:\"wrong0\",pos:[1,\"text\"],tags:[true]}",
+            ),
+            ("a wrong primitive at $.pos[1]", "{kind:\"a\",pos:[1,0],tags:[true]}"),
+            (
+                "a wrong tuple length (one too few) at $.pos",
+                "{kind:\"a\",pos:[1],tags:[true]}",
+            ),
+            (
+                "a wrong primitive at $.tags[0]",
+                "{kind:\"a\",pos:[1,\"text\"],tags:[\"wrong\"]}",
+            ),
+        ] {
+            assert!(tests.contains(&format!(
+                "test(\"isFoo rejects {title}\",()=>assert.equal(isFoo({value}),false));"
+            )));
+        }
+        assert!(!tests.contains("missing required key at $.tags"));
+        assert!(tests.contains("test.skip(\"isNode (no valid Node could be made up)\",()=>{});"));
+        assert!(tests.contains("test.skip(\"isAccount accepts a valid Account (none could be made up)\",()=>{});"));
+        assert!(tests.contains(
+            "test(\"isAccount rejects a missing required key at $.handle\",()=>assert.equal(isAccount({code:\"123\",ids:[1]}),false));"
+        ));
+        assert!(tests.contains(
+            "test(\"isAccount rejects a wrong primitive at $.code\",()=>assert.equal(isAccount({handle:\"text\",code:0,ids:[1]}),false));"
+        ));
+    }
+
+    #[test]
+    fn test_unsupported_aliases() {
+        use crate::lexer::find_interfaces;
+
+        let raw = "
+        type Handler = (x: number) => void;
+        type Keys = keyof Foo;
+        type Flags = { [K in Keys]: boolean };
+        type Check<T> = T extends string ? 1 : 2;
+        type Id = string & { readonly __brand: \"Id\" };
+        interface Foo {
+            id: Id;
+        }
+        ";
+        let interfaces = parse_interfaces(tokenize(find_interfaces(raw.to_string())));
+        let names: Vec<&Key> = interfaces.iter().map(|i| &i.key).collect();
+        assert_eq!(
+            names,
+            vec![&Key::Alias("Id".to_string()), &Key::Name("Foo".to_string())]
+        );
+    }
+}
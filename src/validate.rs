@@ -0,0 +1,562 @@
+use rayon::prelude::{IndexedParallelIterator, IntoParallelIterator, IntoParallelRefIterator, ParallelIterator};
+
+use crate::{
+    cmd::{Extension, Naming, Options},
+    js::{
+        addr_to_string, brand_check, constraint_to_string, generic_call, guard_args, guard_params, has_key, js_string,
+        js_tokens_to_string, literal_token, source_string, to_js_token, type_args, Addr, JSToken,
+    },
+    jsdoc::Doc,
+    lexer::Type,
+    optimize::optimize,
+    parsers::{
+        brand, discriminate, or_branches, unparen, value_to_ts_string, Brand, Discriminated, Entry, Generic, Key, Value,
+    },
+};
+
+const RECEIVED: &str = "x===null?\"null\":Array.isArray(x)?\"array\":typeof x===\"string\"?JSON.stringify(x):typeof x===\"number\"||typeof x===\"boolean\"?String(x):typeof x";
+
+pub fn validation_helpers(extension: Extension) -> String {
+    match extension {
+        Extension::JS => format!("function _received(x){{return({RECEIVED})}}\n"),
+        Extension::TS => format!(
+            "export type ValidationError={{path:string,expected:string,received:string}};\nexport type ValidationResult<T>={{ok:true,value:T}}|{{ok:false,errors:ValidationError[]}};\nfunction _received(x: unknown): string{{return({RECEIVED})}}\n"
+        ),
+        Extension::DTS => "export type ValidationError={path:string,expected:string,received:string};\nexport type ValidationResult<T>={ok:true,value:T}|{ok:false,errors:ValidationError[]};\n".to_string(),
+    }
+}
+
+pub fn validator_dec(name: &str, params: &[String], body: String, extension: Extension, naming: &Naming) -> String {
+    let guards = guard_params(params, extension, naming);
+    let validator = naming.validator(name);
+    match extension {
+        Extension::JS => format!(
+            "export function {validator}(o{guards}){{const e=[];{body}return e.length===0?{{ok:true,value:o}}:{{ok:false,errors:e}}}}"
+        ),
+        Extension::TS => {
+            let t = type_args(params);
+            format!(
+                "export function {validator}{t}(input: unknown{guards}): ValidationResult<{name}{t}>{{const o: any=input;const e: ValidationError[]=[];{body}return e.length===0?{{ok:true,value:o}}:{{ok:false,errors:e}}}}"
+            )
+        }
+        Extension::DTS => {
+            let t = type_args(params);
+            format!("export declare function {validator}{t}(input: unknown{guards}): ValidationResult<{name}{t}>;")
+        }
+    }
+}
+
+/// Same walk as `js::to_js_token`, but every failing check pushes `{ path, expected, received }` into the
+/// JS array named by `errors` instead of short-circuiting.
+pub fn to_js_validator(value: &Value, addr: Addr, errors: &str, depth: usize, options: &Options) -> String {
+    match value {
+        Value::Entry(e) => match &e.key {
+            Key::Name(n) => {
+                let new_addr = [addr.clone(), vec![n.clone()]].concat();
+                format!(
+                    "if(!({})){}{}",
+                    js_tokens_to_string(has_key(addr, n.clone(), e.span, options)),
+                    push_error(&new_addr, errors, &value_to_ts_string(value), "\"undefined\""),
+                    otherwise(
+                        to_js_validators(&e.value, new_addr.clone(), errors, depth, options)
+                            + &refinement_validators(&e.doc, &new_addr, errors)
+                    )
+                )
+            }
+            Key::Optional(n) => {
+                let new_addr = [addr.clone(), vec![n.clone()]].concat();
+                let checks = to_js_validators(&e.value, new_addr.clone(), errors, depth, options)
+                    + &refinement_validators(&e.doc, &new_addr, errors);
+                if checks.is_empty() {
+                    return String::new();
+                }
+                format!(
+                    "if({}){{{checks}}}",
+                    js_tokens_to_string(has_key(addr, n.clone(), e.span, options))
+                )
+            }
+            Key::Generic(g) => match g {
+                Generic::Custom(_) => String::new(),
+                Generic::Ref(n) => match generic_call(n, &e.value, addr.clone(), options) {
+                    JSToken::Apply(_, _, args) => nested_validator(n, &args, &addr, errors, depth, options),
+                    _ => unreachable!(),
+                },
+                Generic::Array => {
+                    let new_addr = [addr.clone(), vec!["0".to_string()]].concat();
+                    format!(
+                        "if(!Array.isArray({})){}{}",
+                        addr_to_string(addr.clone()),
+                        push_error(&addr, errors, &value_to_ts_string(value), &received(&addr)),
+                        otherwise(to_js_validators(&e.value, new_addr, errors, depth, options))
+                    )
+                }
+            },
+            Key::Tuple => {
+                let a = addr_to_string(addr.clone());
+                let elements = e
+                    .value
+                    .par_iter()
+                    .enumerate()
+                    .map(|(i, val)| {
+                        let new_addr = [addr.clone(), vec![i.to_string()]].concat();
+                        to_js_validator(val, new_addr, errors, depth, options)
+                    })
+                    .collect::<Vec<String>>()
+                    .join("");
+                format!(
+                    "if(!Array.isArray({a})){}else if({a}.length!=={}){}{}",
+                    push_error(&addr, errors, &value_to_ts_string(value), &received(&addr)),
+                    e.value.len(),
+                    push_error(
+                        &addr,
+                        errors,
+                        &format!("array of length {}", e.value.len()),
+                        &format!("\"array of length \"+{a}.length")
+                    ),
+                    otherwise(elements)
+                )
+            }
+            Key::None => {
+                let expected = if options.plain_objects {
+                    "plain object"
+                } else {
+                    "object"
+                };
+                format!(
+                    "if({}){}else{{{}}}",
+                    not_object(&addr, options),
+                    push_error(&addr, errors, expected, &received(&addr)),
+                    object_validator(&e.value, None, addr.clone(), errors, depth, options)
+                )
+            }
+            Key::And => match brand(e) {
+                Some(b) => brand_validator(&b, addr, errors, options),
+                None => to_js_validators(&e.value, addr, errors, depth, options),
+            },
+            Key::Ref(n) if e.value.is_empty() => nested_validator(n, &[], &addr, errors, depth, options),
+            Key::Paren | Key::Alias(_) | Key::Ref(_) => to_js_validators(&e.value, addr, errors, depth, options),
+            Key::Or => {
+                let branches = or_branches(e);
+                match discriminate(branches.clone()) {
+                    Some(d) if d.others.is_empty() => discriminated_validator(&d, value, addr, errors, depth, options),
+                    Some(d) => union_validator(
+                        d.others.len() + 1,
+                        |i, errors| match d.others.get(i) {
+                            Some(b) => to_js_validator(b, addr.clone(), errors, depth + 1, options),
+                            None => discriminated_validator(&d, value, addr.clone(), errors, depth + 1, options),
+                        },
+                        value,
+                        &addr,
+                        errors,
+                        depth,
+                        options,
+                    ),
+                    None => union_validator(
+                        branches.len(),
+                        |i, errors| to_js_validator(&branches[i], addr.clone(), errors, depth + 1, options),
+                        value,
+                        &addr,
+                        errors,
+                        depth,
+                        options,
+                    ),
+                }
+            }
+        },
+        Value::Type(_) => {
+            let cond = optimize(to_js_token(value.clone(), addr.clone(), options));
+            if cond == vec![JSToken::True] {
+                return String::new();
+            }
+            format!(
+                "if(!({})){}",
+                js_tokens_to_string(cond),
+                push_error(&addr, errors, &value_to_ts_string(value), &received(&addr))
+            )
+        }
+    }
+}
+
+/// The `else` branch running `checks`, left out if there is nothing to check.
+/// Runs the validator of the recursively referenced interface `name` and adds its errors, with their paths continued
+/// from `addr`.
+fn nested_validator(name: &str, args: &[String], addr: &Addr, errors: &str, depth: usize, options: &Options) -> String {
+    let r = format!("r{depth}");
+    let args: String = args.iter().map(|a| format!(",{a}")).collect();
+    format!(
+        "{{const {r}={}({}{args});if(!{r}.ok)for(const x of {r}.errors){errors}.push({{...x,path:{}+x.path.slice(1)}})}}",
+        options.naming.validator(name),
+        addr_to_string(addr.clone()),
+        js_string(&addr_to_path(addr))
+    )
+}
+
+fn otherwise(checks: String) -> String {
+    if checks.is_empty() {
+        checks
+    } else {
+        format!("else{{{checks}}}")
+    }
+}
+
+/// Checks the primitive of a branded type, then the brand's refinement function if one was given.
+fn brand_validator(b: &Brand, addr: Addr, errors: &str, options: &Options) -> String {
+    let base = js_tokens_to_string(optimize(to_js_token(b.base.clone(), addr.clone(), options)));
+    let base_error = push_error(&addr, errors, &value_to_ts_string(&b.base), &received(&addr));
+    match (brand_check(b, options), &b.name) {
+        (Some(f), Some(name)) => format!(
+            "if(!({base})){base_error}else if(!{}({})){}",
+            f.name,
+            addr_to_string(addr.clone()),
+            push_error(&addr, errors, name, &received(&addr))
+        ),
+        _ => format!("if(!({base})){base_error}"),
+    }
+}
+
+/// Validates every branch into its own array and reports the best-matching one if none of them passes.
+fn union_validator(
+    branches_len: usize,
+    branch: impl Fn(usize, &str) -> String + Sync,
+    value: &Value,
+    addr: &Addr,
+    errors: &str,
+    depth: usize,
+    options: &Options,
+) -> String {
+    let u = format!("u{depth}");
+    let init = vec!["[]"; branches_len].join(",");
+    let decl = match options.extension {
+        Extension::JS => format!("const {u}=[{init}];"),
+        Extension::TS | Extension::DTS => format!("const {u}: ValidationError[][]=[{init}];"),
+    };
+    let checks = (0..branches_len)
+        .into_par_iter()
+        .map(|i| branch(i, &format!("{u}[{i}]")))
+        .collect::<Vec<String>>()
+        .join("");
+    // When every branch already fails at this address, one error naming the whole union reads
+    // better than the errors of an arbitrary branch.
+    format!(
+        "{{{decl}{checks}if({u}.every(x=>x.length!==0)){{const b={u}.reduce((a,x)=>x.length<a.length||x.length===a.length&&x[0].path.length>a[0].path.length?x:a);{errors}.push(...(b.every(x=>x.path==={})?[{}]:b))}}}}",
+        js_string(&addr_to_path(addr)),
+        error_object(addr, &value_to_ts_string(value), &received(addr))
+    )
+}
+
+/// The key count and properties of an object whose type was already checked. Only the presence of the property `skip`
+/// is checked, as a `case` on it already matched.
+fn object_validator(
+    properties: &[Value],
+    skip: Option<&str>,
+    addr: Addr,
+    errors: &str,
+    depth: usize,
+    options: &Options,
+) -> String {
+    let a = addr_to_string(addr.clone());
+    let checks = properties
+        .par_iter()
+        .map(|val| match val {
+            Value::Entry(Entry {
+                key: Key::Name(n),
+                span,
+                ..
+            }) if Some(n.as_str()) == skip => {
+                let new_addr = [addr.clone(), vec![n.clone()]].concat();
+                format!(
+                    "if(!({})){}",
+                    js_tokens_to_string(has_key(addr.clone(), n.clone(), *span, options)),
+                    push_error(&new_addr, errors, &value_to_ts_string(val), "\"undefined\"")
+                )
+            }
+            val => to_js_validator(val, addr.clone(), errors, depth, options),
+        })
+        .collect::<Vec<String>>()
+        .join("");
+    format!(
+        "if(Object.keys({a}).length!=={}){}{checks}",
+        properties.len(),
+        push_error(
+            &addr,
+            errors,
+            &format!("object with {} keys", properties.len()),
+            &format!("\"object with \"+Object.keys({a}).length+\" keys\"")
+        )
+    )
+}
+
+/// The condition of `addr` not being an object an interface describes.
+fn not_object(addr: &Addr, options: &Options) -> String {
+    let a = addr_to_string(addr.clone());
+    let plain = if options.plain_objects {
+        format!("||!{}", js_tokens_to_string(vec![JSToken::PlainObject(addr.clone())]))
+    } else {
+        String::new()
+    };
+    format!("typeof {a}!==\"object\"||{a}===null||Array.isArray({a}){plain}")
+}
+
+/// Switches on the discriminant so only the matching member is validated, after checking once that it is an object.
+fn discriminated_validator(
+    d: &Discriminated,
+    value: &Value,
+    addr: Addr,
+    errors: &str,
+    depth: usize,
+    options: &Options,
+) -> String {
+    let key_addr = [addr.clone(), vec![d.key.clone()]].concat();
+    let cases = d
+        .cases
+        .par_iter()
+        .map(|(labels, branch)| {
+            let labels = labels
+                .iter()
+                .map(|l| format!("case {}:", js_tokens_to_string(vec![literal_token(l)])))
+                .collect::<String>();
+            let Value::Entry(object) = unparen(branch) else {
+                unreachable!("discriminated union members are objects")
+            };
+            format!(
+                "{labels}{{{}}}break;",
+                object_validator(&object.value, Some(&d.key), addr.clone(), errors, depth, options)
+            )
+        })
+        .collect::<String>();
+    let expected = d
+        .cases
+        .iter()
+        .flat_map(|(labels, _)| labels)
+        .map(|l| value_to_ts_string(&Value::Type(l.clone())))
+        .collect::<Vec<String>>()
+        .join(" | ");
+    format!(
+        "if({}){}else switch({}){{{cases}default:{}}}",
+        not_object(&addr, options),
+        push_error(&addr, errors, &value_to_ts_string(value), &received(&addr)),
+        addr_to_string(key_addr.clone()),
+        push_error(&key_addr, errors, &expected, &received(&key_addr))
+    )
+}
+
+fn to_js_validators(values: &[Value], addr: Addr, errors: &str, depth: usize, options: &Options) -> String {
+    values
+        .par_iter()
+        .map(|val| to_js_validator(val, addr.clone(), errors, depth, options))
+        .collect::<Vec<String>>()
+        .join("")
+}
+
+fn refinement_validators(doc: &Option<Doc>, addr: &Addr, errors: &str) -> String {
+    doc.iter()
+        .flat_map(|d| d.constraints())
+        .map(|c| {
+            format!(
+                "if(!{}){}",
+                constraint_to_string(addr_to_string(addr.clone()), &c),
+                push_error(addr, errors, &c.describe(), &received(addr))
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("")
+}
+
+fn push_error(addr: &Addr, errors: &str, expected: &str, received: &str) -> String {
+    format!("{errors}.push({});", error_object(addr, expected, received))
+}
+
+fn error_object(addr: &Addr, expected: &str, received: &str) -> String {
+    format!(
+        "{{path:{},expected:{},received:{}}}",
+        js_string(&addr_to_path(addr)),
+        js_string(expected),
+        received
+    )
+}
+
+fn received(addr: &Addr) -> String {
+    format!("_received({})", addr_to_string(addr.clone()))
+}
+
+/// Renders an address as a JSONPath-like string, e.g. `["o", "foo", "0"]` becomes `$.foo[0]`.
+pub fn addr_to_path(addr: &Addr) -> String {
+    let mut path = "$".to_string();
+    for i in addr.iter().skip(1) {
+        if !i.is_empty() && i.chars().all(|c| c.is_ascii_digit()) {
+            path.push_str(&format!("[{i}]"));
+        } else if is_identifier(i) {
+            path.push_str(&format!(".{i}"));
+        } else {
+            path.push_str(&format!("['{i}']"));
+        }
+    }
+    path
+}
+
+fn is_identifier(string: &str) -> bool {
+    let mut chars = string.chars();
+    match chars.next() {
+        Some(c) if c.is_alphabetic() || c == '_' || c == '$' => {
+            chars.all(|c| c.is_alphanumeric() || c == '_' || c == '$')
+        }
+        _ => false,
+    }
+}
+
+fn throw_first_error(name: &str, error_class: &str) -> String {
+    format!(
+        "if(!r.ok){{const x=r.errors[0];throw new {error_class}(\"Invalid {name} at \"+x.path+\": expected \"+x.expected+\", received \"+x.received)}}"
+    )
+}
+
+pub fn assertion_dec(
+    name: &str,
+    params: &[String],
+    error_class: &str,
+    extension: Extension,
+    naming: &Naming,
+) -> String {
+    let throw = throw_first_error(name, error_class);
+    let (guards, args) = (guard_params(params, extension, naming), guard_args(params, naming));
+    let (assertion, validator) = (naming.assertion(name), naming.validator(name));
+    match extension {
+        Extension::JS => format!("export function {assertion}(o{guards}){{const r={validator}(o{args});{throw}}}"),
+        Extension::TS => {
+            let t = type_args(params);
+            format!(
+                "export function {assertion}{t}(o: unknown{guards}): asserts o is {name}{t}{{const r={validator}(o{args});{throw}}}"
+            )
+        }
+        Extension::DTS => {
+            let t = type_args(params);
+            format!("export declare function {assertion}{t}(o: unknown{guards}): asserts o is {name}{t};")
+        }
+    }
+}
+
+/// `parse<Name>` throws on invalid JSON or shape, `safeParse<Name>` returns the same result as `validate<Name>`. Both
+/// are named after `naming`.
+/// `revive` is the body of `_revive<Name>` (see `to_js_reviver`), if the interface has fields to revive.
+pub fn parse_helpers_dec(
+    name: &str,
+    params: &[String],
+    error_class: &str,
+    revive: Option<String>,
+    extension: Extension,
+    naming: &Naming,
+) -> String {
+    let ts = !matches!(extension, Extension::JS);
+    let (validator, parser, safe_parser) = (naming.validator(name), naming.parser(name), naming.safe_parser(name));
+    let (guards, args) = (guard_params(params, extension, naming), guard_args(params, naming));
+    let t = if ts { type_args(params) } else { String::new() };
+    if let Extension::DTS = extension {
+        return format!(
+            "export declare function {parser}{t}(json: string{guards}): {name}{t};\nexport declare function {safe_parser}{t}(json: string{guards}): ValidationResult<{name}{t}>;"
+        );
+    }
+    let (json, any, result, returns) = if ts {
+        (
+            "json: string",
+            ": any",
+            format!(": ValidationResult<{name}{t}>"),
+            format!(": {name}{t}"),
+        )
+    } else {
+        ("json", "", String::new(), String::new())
+    };
+    let (reviver, parsed) = match revive {
+        Some(body) => (
+            format!("function _revive{name}(o{any}){{{body}return o}}\n"),
+            format!("_revive{name}(JSON.parse(json))"),
+        ),
+        None => (String::new(), "JSON.parse(json)".to_string()),
+    };
+    format!(
+        "{reviver}export function {parser}{t}({json}{guards}){returns}{{const r={validator}({parsed}{args});{}return r.value}}\nexport function {safe_parser}{t}({json}{guards}){result}{{let o{};try{{o={parsed}}}catch(err){{return{{ok:false,errors:[{{path:\"$\",expected:\"JSON\",received:String(err)}}]}}}}return {validator}(o{args})}}",
+        throw_first_error(name, error_class),
+        if ts { ": unknown" } else { "" },
+    )
+}
+
+/// Converts JSON representations of `bigint` and `Date` fields back in place. `target` is the JS expression
+/// holding the value. Returns an empty string if nothing below `value` needs reviving.
+pub fn to_js_reviver(value: &Value, target: &str, depth: usize) -> String {
+    match value {
+        Value::Entry(e) => match &e.key {
+            Key::Name(n) | Key::Optional(n) => {
+                let target = format!("{target}[{}]", source_string(n));
+                to_js_revivers(&e.value, &target, depth)
+            }
+            Key::Generic(Generic::Array) => {
+                let i = format!("i{depth}");
+                let body = to_js_revivers(&e.value, &format!("{target}[{i}]"), depth + 1);
+                if body.is_empty() {
+                    return body;
+                }
+                format!("if(Array.isArray({target}))for(let {i}=0;{i}<{target}.length;{i}++){{{body}}}")
+            }
+            Key::Tuple => {
+                let body = e
+                    .value
+                    .iter()
+                    .enumerate()
+                    .map(|(i, val)| to_js_reviver(val, &format!("{target}[{i}]"), depth))
+                    .collect::<Vec<String>>()
+                    .join("");
+                if body.is_empty() {
+                    return body;
+                }
+                format!("if(Array.isArray({target})){{{body}}}")
+            }
+            Key::None => {
+                let body = to_js_revivers(&e.value, target, depth);
+                if body.is_empty() {
+                    return body;
+                }
+                format!("if(typeof {target}===\"object\"&&{target}!==null){{{body}}}")
+            }
+            Key::Or => {
+                // A branch that accepts the raw JSON value wins over a conversion, e.g. `string | Date`.
+                let branches = or_branches(e);
+                let accepts_raw = |t: &Type| {
+                    branches.iter().any(|b| {
+                        matches!(
+                            (t, b),
+                            (
+                                _,
+                                Value::Type(Type::Any | Type::Unknown | Type::String | Type::StringLit(_))
+                            ) | (Type::BigInt, Value::Type(Type::Number | Type::NumberLit(_)))
+                        )
+                    })
+                };
+                branches
+                    .iter()
+                    .filter(|b| match b {
+                        Value::Type(t @ Type::BigInt) | Value::Type(t @ Type::Custom(_)) => !accepts_raw(t),
+                        _ => true,
+                    })
+                    .map(|b| to_js_reviver(b, target, depth))
+                    .collect::<Vec<String>>()
+                    .join("")
+            }
+            Key::And | Key::Paren | Key::Alias(_) | Key::Ref(_) => to_js_revivers(&e.value, target, depth),
+            Key::Generic(Generic::Custom(_) | Generic::Ref(_)) => String::new(),
+        },
+        Value::Type(Type::BigInt) => {
+            format!("if((typeof {target}===\"string\"||typeof {target}===\"number\")&&/^-?\\d+$/.test(String({target}))){target}=BigInt({target});")
+        }
+        Value::Type(Type::Custom(name)) if name == "Date" => {
+            format!("if(typeof {target}===\"string\"&&!isNaN(Date.parse({target}))){target}=new Date({target});")
+        }
+        Value::Type(_) => String::new(),
+    }
+}
+
+fn to_js_revivers(values: &[Value], target: &str, depth: usize) -> String {
+    values
+        .iter()
+        .map(|val| to_js_reviver(val, target, depth))
+        .collect::<Vec<String>>()
+        .join("")
+}
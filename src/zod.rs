@@ -0,0 +1,255 @@
+use rayon::prelude::{IndexedParallelIterator, IntoParallelRefIterator, ParallelIterator};
+
+use crate::cmd::{Extension, Options};
+use crate::js::{brand_check, js_string, source_string};
+use crate::lexer::Type;
+use crate::parsers::{brand, or_branches, value_to_ts_string, Entry, Generic, Key, Value};
+
+/// Translates the interfaces into `export const <Name>Schema = z.object(...)` declarations. References to interfaces
+/// declared further down, and back to an interface being defined, go through `z.lazy`.
+pub fn interfaces_to_zod(interfaces: &[Entry], options: &Options) -> String {
+    let names: Vec<&String> = interfaces
+        .iter()
+        .map(|i| match &i.key {
+            Key::Name(name) => name,
+            _ => panic!("Name of interface not found"),
+        })
+        .collect();
+    let ts = !matches!(options.extension, Extension::JS);
+    let schemas = interfaces
+        .par_iter()
+        .enumerate()
+        .map(|(index, i)| {
+            let name = names[index];
+            let object = Value::Entry(Entry {
+                key: Key::None,
+                value: i.value.clone(),
+                doc: None,
+                params: Vec::new(),
+                span: i.span,
+            });
+            let schema = Schema {
+                names: &names,
+                index,
+                options,
+            }
+            .value(&object);
+            let schema = match &i.doc {
+                Some(doc) if !doc.description.is_empty() => format!("{schema}.describe({})", js_string(&doc.description)),
+                _ => schema,
+            };
+            if !i.params.is_empty() {
+                let params = i.params.join(", ");
+                return if ts {
+                    let bounds = i
+                        .params
+                        .iter()
+                        .map(|p| format!("{p} extends z.ZodTypeAny"))
+                        .collect::<Vec<String>>()
+                        .join(", ");
+                    let args = i.params.iter().map(|p| format!("{p}: {p}")).collect::<Vec<String>>().join(", ");
+                    format!("export const {name}Schema = <{bounds}>({args}) => {schema};\n")
+                } else {
+                    format!("export const {name}Schema = ({params}) => {schema};\n")
+                };
+            }
+            match (ts, is_recursive(&i.value)) {
+                (true, true) => format!(
+                    "export type {name} = {};\nexport const {name}Schema: z.ZodType<{name}> = z.lazy(() => {schema});\n",
+                    value_to_ts_string(&without_aliases(&object, &names))
+                ),
+                (true, false) => {
+                    format!("export const {name}Schema = {schema};\nexport type {name} = z.infer<typeof {name}Schema>;\n")
+                }
+                (false, true) => format!("export const {name}Schema = z.lazy(() => {schema});\n"),
+                (false, false) => format!("export const {name}Schema = {schema};\n"),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+    let imports = options
+        .guards
+        .iter()
+        .map(|(_, f)| f)
+        .chain(options.brands.iter().map(|(_, f)| f))
+        .collect();
+    format!(
+        "import {{ z }} from \"zod\";\n{}\n{}",
+        crate::js::imports_dec(imports),
+        schemas
+    )
+}
+
+struct Schema<'a> {
+    names: &'a [&'a String],
+    /// Position of the interface being translated, to tell forward references apart.
+    index: usize,
+    options: &'a Options,
+}
+
+impl Schema<'_> {
+    fn value(&self, value: &Value) -> String {
+        match value {
+            Value::Type(t) => self.leaf(t),
+            Value::Entry(e) => match &e.key {
+                Key::None => {
+                    let props = e
+                        .value
+                        .iter()
+                        .filter_map(|i| match i {
+                            Value::Entry(p @ Entry { key: Key::Name(n), .. }) => {
+                                Some(format!("{}: {}", prop_key(n), self.property(p)))
+                            }
+                            Value::Entry(
+                                p @ Entry {
+                                    key: Key::Optional(n), ..
+                                },
+                            ) => Some(format!("{}: {}.optional()", prop_key(n), self.property(p))),
+                            _ => None,
+                        })
+                        .collect::<Vec<String>>();
+                    format!("z.object({{ {} }}).strict()", props.join(", "))
+                }
+                Key::Ref(n) => match self.names.iter().position(|i| *i == n) {
+                    Some(i) if i < self.index && !e.value.is_empty() => format!("{n}Schema"),
+                    Some(_) => format!("z.lazy(() => {n}Schema)"),
+                    None => self.values(&e.value),
+                },
+                Key::Name(_) | Key::Optional(_) | Key::Paren | Key::Alias(_) => self.values(&e.value),
+                Key::Generic(Generic::Array) => format!("z.array({})", self.values(&e.value)),
+                Key::Generic(Generic::Ref(n)) => {
+                    let args: Vec<String> = e.value.iter().map(|i| self.value(i)).collect();
+                    format!("z.lazy(() => {n}Schema({}))", args.join(", "))
+                }
+                Key::Generic(Generic::Custom(_)) => "z.unknown()".to_string(),
+                Key::Tuple => format!(
+                    "z.tuple([{}])",
+                    e.value
+                        .iter()
+                        .map(|i| self.value(i))
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                ),
+                Key::Or => format!(
+                    "z.union([{}])",
+                    or_branches(e)
+                        .iter()
+                        .map(|i| self.value(i))
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                ),
+                Key::And => match brand(e) {
+                    Some(b) => {
+                        let base = self.value(&b.base);
+                        let refined = match brand_check(&b, self.options) {
+                            Some(f) => format!("{base}.refine({})", f.name),
+                            None => base,
+                        };
+                        match (&b.name, self.options.extension) {
+                            (Some(name), Extension::TS | Extension::DTS) => {
+                                format!("{refined}.brand<{}>()", source_string(name))
+                            }
+                            _ => refined,
+                        }
+                    }
+                    None => self.values(&e.value),
+                },
+            },
+        }
+    }
+
+    /// Schemas that must all pass, intersected if there is more than one.
+    fn values(&self, values: &[Value]) -> String {
+        let mut schemas = values
+            .iter()
+            .filter(|i| !matches!(i, Value::Type(Type::Oper(_) | Type::Punct(_))))
+            .map(|i| self.value(i));
+        let first = schemas.next().unwrap_or_else(|| "z.unknown()".to_string());
+        schemas.fold(first, |a, i| format!("z.intersection({a}, {i})"))
+    }
+
+    fn property(&self, property: &Entry) -> String {
+        let schema = self.values(&property.value);
+        match &property.doc {
+            Some(doc) if !doc.description.is_empty() => format!("{schema}.describe({})", js_string(&doc.description)),
+            _ => schema,
+        }
+    }
+
+    fn leaf(&self, t: &Type) -> String {
+        match t {
+            Type::String => "z.string()".to_string(),
+            Type::Number => "z.number()".to_string(),
+            Type::Boolean => "z.boolean()".to_string(),
+            Type::BigInt => "z.bigint()".to_string(),
+            Type::Symbol => "z.symbol()".to_string(),
+            Type::Null => "z.null()".to_string(),
+            Type::Undefined => "z.undefined()".to_string(),
+            Type::Any => "z.any()".to_string(),
+            Type::Unknown => "z.unknown()".to_string(),
+            Type::Function => "z.function()".to_string(),
+            Type::True => "z.literal(true)".to_string(),
+            Type::False => "z.literal(false)".to_string(),
+            Type::StringLit(s) => format!("z.literal({})", source_string(s)),
+            Type::NumberLit(n) => format!("z.literal({n})"),
+            Type::Param(p) => p.clone(),
+            Type::Custom(name) => match self.options.guards.iter().find(|(i, _)| i == name) {
+                Some((_, guard)) => self.custom(name, &guard.name),
+                None if name == "Date" => "z.date()".to_string(),
+                None => self.custom(name, "(x) => typeof x === \"object\" && x !== null"),
+            },
+            Type::Object => self.custom("object", "(x) => typeof x === \"object\" && x !== null"),
+            Type::Oper(_) | Type::Punct(_) => String::new(),
+        }
+    }
+
+    fn custom(&self, name: &str, check: &str) -> String {
+        match self.options.extension {
+            Extension::JS => format!("z.custom({check})"),
+            Extension::TS | Extension::DTS => format!("z.custom<{name}>({check})"),
+        }
+    }
+}
+
+/// Whether the expansion of an interface refers back to an interface it is part of.
+fn is_recursive(values: &[Value]) -> bool {
+    values.iter().any(|i| match i {
+        Value::Entry(Entry {
+            key: Key::Ref(_),
+            value,
+            ..
+        }) if value.is_empty() => true,
+        Value::Entry(e) => is_recursive(&e.value),
+        Value::Type(_) => false,
+    })
+}
+
+/// Replaces references to aliases, which have no declaration in the output, with their right-hand side.
+fn without_aliases(value: &Value, names: &[&String]) -> Value {
+    match value {
+        Value::Entry(e) => Value::Entry(Entry {
+            key: match &e.key {
+                Key::Ref(n) if !names.contains(&n) => Key::Paren,
+                key => key.clone(),
+            },
+            value: e.value.iter().map(|i| without_aliases(i, names)).collect(),
+            doc: e.doc.clone(),
+            params: e.params.clone(),
+            span: e.span,
+        }),
+        value => value.clone(),
+    }
+}
+
+fn prop_key(name: &str) -> String {
+    let mut chars = name.chars();
+    let identifier = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+    if identifier {
+        name.to_string()
+    } else {
+        source_string(name)
+    }
+}